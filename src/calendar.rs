@@ -4,10 +4,16 @@
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use esp_idf_svc::nvs::{EspNvs, EspNvsPartition, NvsDefault};
 use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use log::warn;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
+use std::time::{Duration as StdDuration, Instant};
+
+use crate::retry::{is_retryable_status, retry_with_backoff, Attempt, RetryConfig};
 
 // Configuration
 pub const WORK_START_HOUR: u32 = 9;
@@ -16,6 +22,11 @@ pub const CALENDAR_ID: &str = "foodneutrino@gmail.com";
 pub const SERVICE_ACCOUNT_FILE: &str = "free-time-calc-7daa6babd0ae.json";
 const SCOPES: &str = "https://www.googleapis.com/auth/calendar.readonly";
 
+const TOKEN_NVS_NAMESPACE: &str = "calendar";
+const REFRESH_TOKEN_NVS_KEY: &str = "gcal_refresh";
+const TOKEN_EXPIRY_MARGIN: StdDuration = StdDuration::from_secs(60);
+const OAUTH_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+
 #[derive(Debug, Deserialize)]
 struct ServiceAccountKey {
     client_email: String,
@@ -35,6 +46,8 @@ struct Claims {
 #[derive(Debug, Deserialize)]
 struct TokenResponse {
     access_token: String,
+    refresh_token: Option<String>,
+    expires_in: Option<i64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -42,34 +55,56 @@ pub struct EventsResponse {
     pub items: Option<Vec<Event>>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Event {
     pub summary: Option<String>,
     pub start: Option<EventTime>,
     pub end: Option<EventTime>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct EventTime {
     #[serde(rename = "dateTime")]
     pub date_time: Option<String>,
     pub date: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BusyPeriod {
     pub start: NaiveDateTime,
     pub end: NaiveDateTime,
     pub title: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FreeSlot {
     pub start: NaiveDateTime,
     pub end: NaiveDateTime,
 }
 
-fn get_access_token(key: &ServiceAccountKey) -> Result<String> {
+/// Send a request built fresh on each attempt (reqwest's `RequestBuilder` isn't
+/// reusable), retrying on a dropped connection or a 429/5xx response.
+fn send_json<T: DeserializeOwned>(build: impl Fn() -> reqwest::blocking::RequestBuilder) -> Result<T> {
+    retry_with_backoff(&RetryConfig::default(), || match build().send() {
+        Ok(response) => {
+            let status = response.status().as_u16();
+            if is_retryable_status(status) {
+                return Attempt::Retryable(anyhow::anyhow!("request returned retryable status {}", status));
+            }
+            if status >= 400 {
+                return Attempt::Fatal(anyhow::anyhow!("request returned status {}", status));
+            }
+            match response.json::<T>() {
+                Ok(value) => Attempt::Success(value),
+                Err(e) => Attempt::Fatal(anyhow::Error::new(e).context("Failed to parse JSON response")),
+            }
+        }
+        Err(e) if e.is_timeout() || e.is_connect() => Attempt::Retryable(e.into()),
+        Err(e) => Attempt::Fatal(e.into()),
+    })
+}
+
+fn get_access_token(key: &ServiceAccountKey) -> Result<TokenResponse> {
     let now = Utc::now().timestamp();
     let claims = Claims {
         iss: key.client_email.clone(),
@@ -86,21 +121,15 @@ fn get_access_token(key: &ServiceAccountKey) -> Result<String> {
     let jwt = encode(&header, &claims, &encoding_key).context("Failed to encode JWT")?;
 
     let client = reqwest::blocking::Client::new();
-    let response: TokenResponse = client
-        .post(&key.token_uri)
-        .form(&[
+    send_json(|| {
+        client.post(&key.token_uri).form(&[
             ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
-            ("assertion", &jwt),
+            ("assertion", jwt.as_str()),
         ])
-        .send()
-        .context("Failed to request access token")?
-        .json()
-        .context("Failed to parse token response")?;
-
-    Ok(response.access_token)
+    })
 }
 
-pub fn get_credentials() -> Result<String> {
+fn read_service_account_key() -> Result<ServiceAccountKey> {
     if !Path::new(SERVICE_ACCOUNT_FILE).exists() {
         anyhow::bail!(
             "Service account key file '{}' not found.\n\
@@ -112,22 +141,131 @@ pub fn get_credentials() -> Result<String> {
     let key_json = fs::read_to_string(SERVICE_ACCOUNT_FILE)
         .context("Failed to read service account file")?;
 
-    let key: ServiceAccountKey =
-        serde_json::from_str(&key_json).context("Failed to parse service account JSON")?;
+    serde_json::from_str(&key_json).context("Failed to parse service account JSON")
+}
+
+/// Caches the Calendar access token in memory and tracks its expiry, so a long-running
+/// device doesn't silently 401 mid-session. When the auth flow yields a refresh token
+/// (a user OAuth client, rather than this repo's default service account), that refresh
+/// token is persisted in NVS and used to mint new access tokens without re-prompting.
+pub struct TokenStore {
+    nvs: EspNvs<NvsDefault>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    expires_at: Option<Instant>,
+}
+
+impl TokenStore {
+    pub fn new(nvs_partition: EspNvsPartition<NvsDefault>) -> Result<Self> {
+        let nvs = EspNvs::new(nvs_partition, TOKEN_NVS_NAMESPACE, true)
+            .context("Failed to open calendar NVS namespace")?;
+
+        let mut store = Self {
+            nvs,
+            client_id: option_env!("GOOGLE_OAUTH_CLIENT_ID").map(str::to_string),
+            client_secret: option_env!("GOOGLE_OAUTH_CLIENT_SECRET").map(str::to_string),
+            access_token: None,
+            refresh_token: None,
+            expires_at: None,
+        };
+        store.refresh_token = store.load_refresh_token();
+        Ok(store)
+    }
+
+    fn load_refresh_token(&mut self) -> Option<String> {
+        let mut buf = [0u8; 256];
+        match self.nvs.get_str(REFRESH_TOKEN_NVS_KEY, &mut buf) {
+            Ok(token) => token.map(str::to_string),
+            Err(e) => {
+                warn!("Failed to read Calendar refresh token from NVS: {:?}", e);
+                None
+            }
+        }
+    }
+
+    fn persist_refresh_token(&mut self, token: &str) {
+        if let Err(e) = self.nvs.set_str(REFRESH_TOKEN_NVS_KEY, token) {
+            warn!("Failed to persist Calendar refresh token to NVS: {:?}", e);
+        }
+    }
+
+    fn is_stale(&self) -> bool {
+        match self.expires_at {
+            Some(expires_at) => Instant::now() + TOKEN_EXPIRY_MARGIN >= expires_at,
+            None => true,
+        }
+    }
+
+    fn store_token(&mut self, token: TokenResponse) {
+        if let Some(refresh_token) = &token.refresh_token {
+            self.refresh_token = Some(refresh_token.clone());
+            self.persist_refresh_token(refresh_token);
+        }
+        self.expires_at = token
+            .expires_in
+            .map(|secs| Instant::now() + StdDuration::from_secs(secs.max(0) as u64));
+        self.access_token = Some(token.access_token);
+    }
+
+    fn refresh_via_oauth(&mut self) -> Result<()> {
+        let (client_id, client_secret, refresh_token) =
+            match (&self.client_id, &self.client_secret, &self.refresh_token) {
+                (Some(id), Some(secret), Some(refresh)) => {
+                    (id.clone(), secret.clone(), refresh.clone())
+                }
+                _ => anyhow::bail!("No refresh token available to refresh the Calendar access token"),
+            };
+
+        let client = reqwest::blocking::Client::new();
+        let response: TokenResponse = send_json(|| {
+            client.post(OAUTH_TOKEN_URL).form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token.as_str()),
+                ("client_id", client_id.as_str()),
+                ("client_secret", client_secret.as_str()),
+            ])
+        })
+        .context("Failed to refresh Calendar access token")?;
+
+        self.store_token(response);
+        Ok(())
+    }
 
-    get_access_token(&key)
+    /// Return a valid access token, minting or refreshing one first if the cached
+    /// token is missing or within `TOKEN_EXPIRY_MARGIN` of expiring.
+    pub fn access_token(&mut self) -> Result<String> {
+        if !self.is_stale() {
+            if let Some(token) = &self.access_token {
+                return Ok(token.clone());
+            }
+        }
+
+        if self.refresh_token.is_some() {
+            self.refresh_via_oauth()?;
+        } else {
+            let key = read_service_account_key()?;
+            let token = get_access_token(&key)?;
+            self.store_token(token);
+        }
+
+        self.access_token
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Failed to obtain a Calendar access token"))
+    }
 }
 
-pub fn get_todays_events(access_token: &str) -> Result<Vec<Event>> {
-    let now = Local::now();
-    let start_of_day = now
-        .date_naive()
-        .and_hms_opt(0, 0, 0)
-        .unwrap();
-    let end_of_day = start_of_day + Duration::days(1);
+/// Fetch events in `[start, end)`, both given as local calendar dates.
+pub fn get_events_in_range(
+    token_store: &mut TokenStore,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<Vec<Event>> {
+    let access_token = token_store.access_token()?;
 
-    let time_min = format!("{}Z", start_of_day.format("%Y-%m-%dT%H:%M:%S"));
-    let time_max = format!("{}Z", end_of_day.format("%Y-%m-%dT%H:%M:%S"));
+    let time_min = format!("{}Z", start.and_hms_opt(0, 0, 0).unwrap().format("%Y-%m-%dT%H:%M:%S"));
+    let time_max = format!("{}Z", end.and_hms_opt(0, 0, 0).unwrap().format("%Y-%m-%dT%H:%M:%S"));
 
     let url = format!(
         "https://www.googleapis.com/calendar/v3/calendars/{}/events",
@@ -135,23 +273,41 @@ pub fn get_todays_events(access_token: &str) -> Result<Vec<Event>> {
     );
 
     let client = reqwest::blocking::Client::new();
-    let response: EventsResponse = client
-        .get(&url)
-        .bearer_auth(access_token)
-        .query(&[
+    let response: EventsResponse = send_json(|| {
+        client.get(&url).bearer_auth(&access_token).query(&[
             ("timeMin", time_min.as_str()),
             ("timeMax", time_max.as_str()),
             ("singleEvents", "true"),
             ("orderBy", "startTime"),
         ])
-        .send()
-        .context("Failed to fetch calendar events")?
-        .json()
-        .context("Failed to parse events response")?;
+    })
+    .context("Failed to fetch calendar events")?;
 
     Ok(response.items.unwrap_or_default())
 }
 
+pub fn get_todays_events(token_store: &mut TokenStore) -> Result<Vec<Event>> {
+    let today = Local::now().date_naive();
+    get_events_in_range(token_store, today, today + Duration::days(1))
+}
+
+/// Fetch the next seven days of events and compute each day's busy periods and free
+/// slots, for a week-ahead planning view instead of only today.
+pub fn get_week_ahead(
+    token_store: &mut TokenStore,
+) -> Result<Vec<(NaiveDate, Vec<BusyPeriod>, Vec<FreeSlot>)>> {
+    let today = Local::now().date_naive();
+    let events = get_events_in_range(token_store, today, today + Duration::days(7))?;
+
+    Ok((0..7)
+        .map(|offset| {
+            let day = today + Duration::days(offset);
+            let (busy_periods, free_slots) = calculate_free_time_for_day(&events, day);
+            (day, busy_periods, free_slots)
+        })
+        .collect())
+}
+
 fn urlencoded(s: &str) -> String {
     s.replace("@", "%40")
 }
@@ -179,12 +335,12 @@ pub fn parse_event_time(time: &Option<EventTime>) -> Option<NaiveDateTime> {
     None
 }
 
-pub fn calculate_free_time(events: &[Event]) -> (Vec<BusyPeriod>, Vec<FreeSlot>) {
-    let today = Local::now().date_naive();
-    let work_start = today
-        .and_time(NaiveTime::from_hms_opt(WORK_START_HOUR, 0, 0).unwrap());
-    let work_end = today
-        .and_time(NaiveTime::from_hms_opt(WORK_END_HOUR, 0, 0).unwrap());
+/// Compute busy periods and free slots for `day` out of a (possibly multi-day) event
+/// list, clipping each event to that day's working hours and ignoring events that
+/// don't touch it.
+pub fn calculate_free_time_for_day(events: &[Event], day: NaiveDate) -> (Vec<BusyPeriod>, Vec<FreeSlot>) {
+    let work_start = day.and_time(NaiveTime::from_hms_opt(WORK_START_HOUR, 0, 0).unwrap());
+    let work_end = day.and_time(NaiveTime::from_hms_opt(WORK_END_HOUR, 0, 0).unwrap());
 
     let mut busy_periods: Vec<BusyPeriod> = events
         .iter()
@@ -192,6 +348,10 @@ pub fn calculate_free_time(events: &[Event]) -> (Vec<BusyPeriod>, Vec<FreeSlot>)
             let start = parse_event_time(&event.start)?;
             let end = parse_event_time(&event.end)?;
 
+            if start >= work_end || end <= work_start {
+                return None;
+            }
+
             // Clip to working hours
             let start = start.max(work_start);
             let end = end.min(work_end);
@@ -234,6 +394,99 @@ pub fn calculate_free_time(events: &[Event]) -> (Vec<BusyPeriod>, Vec<FreeSlot>)
     (busy_periods, free_slots)
 }
 
+pub fn calculate_free_time(events: &[Event]) -> (Vec<BusyPeriod>, Vec<FreeSlot>) {
+    calculate_free_time_for_day(events, Local::now().date_naive())
+}
+
+const EVENT_CACHE_NVS_NAMESPACE: &str = "event_cache";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedDay {
+    events: Vec<Event>,
+    busy_periods: Vec<BusyPeriod>,
+    free_slots: Vec<FreeSlot>,
+}
+
+/// Caches a day's fetched events (and the busy/free slots derived from them) in NVS,
+/// keyed by date, so a transient Wi-Fi failure at boot doesn't leave the display blank.
+pub struct EventCache {
+    nvs: EspNvs<NvsDefault>,
+}
+
+impl EventCache {
+    pub fn new(nvs_partition: EspNvsPartition<NvsDefault>) -> Result<Self> {
+        let nvs = EspNvs::new(nvs_partition, EVENT_CACHE_NVS_NAMESPACE, true)
+            .context("Failed to open event cache NVS namespace")?;
+        Ok(Self { nvs })
+    }
+
+    fn key_for(day: NaiveDate) -> String {
+        format!("d{}", day.format("%y%m%d"))
+    }
+
+    fn load(&self, day: NaiveDate) -> Option<CachedDay> {
+        let mut buf = [0u8; 4096];
+        let json = match self.nvs.get_str(&Self::key_for(day), &mut buf) {
+            Ok(Some(json)) => json,
+            Ok(None) => return None,
+            Err(e) => {
+                warn!("Failed to read cached events from NVS: {:?}", e);
+                return None;
+            }
+        };
+
+        match serde_json::from_str(json) {
+            Ok(cached) => Some(cached),
+            Err(e) => {
+                warn!("Failed to deserialize cached events: {:?}", e);
+                None
+            }
+        }
+    }
+
+    fn store(&mut self, day: NaiveDate, cached: &CachedDay) {
+        match serde_json::to_string(cached) {
+            Ok(json) => {
+                if let Err(e) = self.nvs.set_str(&Self::key_for(day), &json) {
+                    warn!("Failed to persist cached events to NVS: {:?}", e);
+                }
+            }
+            Err(e) => warn!("Failed to serialize cached events: {:?}", e),
+        }
+    }
+}
+
+/// Fetch today's events live, falling back to the last cached copy if the network
+/// request fails, and refreshing the cache whenever a live fetch succeeds.
+pub fn get_todays_events_with_cache(
+    token_store: &mut TokenStore,
+    cache: &mut EventCache,
+) -> Result<(Vec<Event>, Vec<BusyPeriod>, Vec<FreeSlot>)> {
+    let today = Local::now().date_naive();
+
+    match get_todays_events(token_store) {
+        Ok(events) => {
+            let (busy_periods, free_slots) = calculate_free_time_for_day(&events, today);
+            cache.store(
+                today,
+                &CachedDay {
+                    events: events.clone(),
+                    busy_periods: busy_periods.clone(),
+                    free_slots: free_slots.clone(),
+                },
+            );
+            Ok((events, busy_periods, free_slots))
+        }
+        Err(e) => {
+            warn!("Live calendar fetch failed ({}), falling back to cached events", e);
+            match cache.load(today) {
+                Some(cached) => Ok((cached.events, cached.busy_periods, cached.free_slots)),
+                None => Err(e),
+            }
+        }
+    }
+}
+
 pub fn format_duration(duration: Duration) -> String {
     let total_minutes = duration.num_minutes();
     let hours = total_minutes / 60;
@@ -245,3 +498,90 @@ pub fn format_duration(duration: Duration) -> String {
         (_, m) => format!("{}m", m),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(summary: &str, start: &str, end: &str) -> Event {
+        Event {
+            summary: Some(summary.to_string()),
+            start: Some(EventTime {
+                date_time: Some(start.to_string()),
+                date: None,
+            }),
+            end: Some(EventTime {
+                date_time: Some(end.to_string()),
+                date: None,
+            }),
+        }
+    }
+
+    fn day() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 7, 29).unwrap()
+    }
+
+    #[test]
+    fn free_time_fills_the_whole_work_day_with_no_events() {
+        let (busy, free) = calculate_free_time_for_day(&[], day());
+        assert!(busy.is_empty());
+        assert_eq!(free.len(), 1);
+        assert_eq!(free[0].start, day().and_hms_opt(WORK_START_HOUR, 0, 0).unwrap());
+        assert_eq!(free[0].end, day().and_hms_opt(WORK_END_HOUR, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn an_event_spanning_midnight_into_the_day_is_clipped_to_work_start() {
+        // Started the evening before, ends mid-morning on `day()`.
+        let events = [event("Overnight on-call", "2026-07-28T22:00:00Z", "2026-07-29T10:00:00Z")];
+        let (busy, free) = calculate_free_time_for_day(&events, day());
+        assert_eq!(busy.len(), 1);
+        assert_eq!(busy[0].start, day().and_hms_opt(WORK_START_HOUR, 0, 0).unwrap());
+        assert_eq!(busy[0].end, day().and_hms_opt(10, 0, 0).unwrap());
+        assert_eq!(free[0].start, day().and_hms_opt(10, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn an_event_spanning_midnight_out_of_the_day_is_clipped_to_work_end() {
+        // Starts mid-afternoon on `day()`, continues into the next day.
+        let events = [event("Overnight incident", "2026-07-29T15:00:00Z", "2026-07-30T03:00:00Z")];
+        let (busy, _free) = calculate_free_time_for_day(&events, day());
+        assert_eq!(busy.len(), 1);
+        assert_eq!(busy[0].start, day().and_hms_opt(15, 0, 0).unwrap());
+        assert_eq!(busy[0].end, day().and_hms_opt(WORK_END_HOUR, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn an_event_fully_spanning_the_day_blocks_the_whole_working_day() {
+        // Multi-day PTO: starts the day before and ends the day after, so it never
+        // touches `day()`'s boundary but fully covers its working hours.
+        let events = [event("On vacation", "2026-07-28T00:00:00Z", "2026-07-30T00:00:00Z")];
+        let (busy, free) = calculate_free_time_for_day(&events, day());
+        assert_eq!(busy.len(), 1);
+        assert_eq!(busy[0].start, day().and_hms_opt(WORK_START_HOUR, 0, 0).unwrap());
+        assert_eq!(busy[0].end, day().and_hms_opt(WORK_END_HOUR, 0, 0).unwrap());
+        assert!(free.is_empty());
+    }
+
+    #[test]
+    fn an_event_on_neither_boundary_day_is_ignored() {
+        let events = [event("Some other day", "2026-07-27T09:00:00Z", "2026-07-27T10:00:00Z")];
+        let (busy, free) = calculate_free_time_for_day(&events, day());
+        assert!(busy.is_empty());
+        assert_eq!(free.len(), 1);
+    }
+
+    #[test]
+    fn busy_periods_split_free_time_around_them() {
+        let events = [
+            event("Standup", "2026-07-29T09:00:00Z", "2026-07-29T09:30:00Z"),
+            event("Lunch", "2026-07-29T12:00:00Z", "2026-07-29T13:00:00Z"),
+        ];
+        let (busy, free) = calculate_free_time_for_day(&events, day());
+        assert_eq!(busy.len(), 2);
+        assert_eq!(free.len(), 2);
+        assert_eq!(free[0].start, day().and_hms_opt(9, 30, 0).unwrap());
+        assert_eq!(free[0].end, day().and_hms_opt(12, 0, 0).unwrap());
+        assert_eq!(free[1].end, day().and_hms_opt(WORK_END_HOUR, 0, 0).unwrap());
+    }
+}
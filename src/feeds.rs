@@ -0,0 +1,196 @@
+//! RSS/Atom Feed Module
+//!
+//! Fetches a configurable list of RSS/Atom feeds (standup notes, team blog, on-call
+//! rotations) and surfaces entries published since the last run, so time-sensitive
+//! external updates can feed into the daily plan alongside Calendar and Notion.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use esp_idf_svc::nvs::{EspNvs, EspNvsPartition, NvsDefault};
+use log::warn;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+
+use crate::retry::{is_retryable_status, retry_with_backoff, Attempt, RetryConfig};
+
+const FEED_NVS_NAMESPACE: &str = "feeds";
+const SEEN_IDS_KEY: &str = "seen_ids";
+const LAST_RUN_KEY: &str = "last_run";
+
+/// How many entry IDs to remember for dedup. NVS string values are capped around 4000
+/// bytes, so this bounds the persisted list well under that.
+const MAX_SEEN_IDS: usize = 150;
+
+pub struct FeedEntry {
+    pub feed_title: String,
+    pub title: String,
+    pub link: Option<String>,
+    pub published: Option<DateTime<Utc>>,
+}
+
+/// Fetches feeds and remembers which entries have already been surfaced, so the same
+/// headline isn't shown every poll cycle. `last_run` additionally gates by publish date,
+/// so a fresh device doesn't dump each feed's entire backlog into its first plan; it
+/// defaults to "now" when nothing has been persisted yet, rather than "the beginning of
+/// time", for the same reason.
+pub struct FeedSource {
+    client: reqwest::blocking::Client,
+    urls: Vec<String>,
+    nvs: EspNvs<NvsDefault>,
+    seen_ids: VecDeque<u64>,
+    last_run: DateTime<Utc>,
+}
+
+impl FeedSource {
+    pub fn new(nvs_partition: EspNvsPartition<NvsDefault>, urls: Vec<String>) -> Result<Self> {
+        let nvs = EspNvs::new(nvs_partition, FEED_NVS_NAMESPACE, true)
+            .context("Failed to open feeds NVS namespace")?;
+        let seen_ids = load_seen_ids(&nvs);
+        let last_run = load_last_run(&nvs);
+
+        Ok(Self {
+            client: reqwest::blocking::Client::new(),
+            urls,
+            nvs,
+            seen_ids,
+            last_run,
+        })
+    }
+
+    /// Fetch every configured feed and return entries not already seen and published
+    /// since the last run. A single feed failing to fetch or parse is logged and
+    /// skipped rather than aborting the rest.
+    pub fn fetch_recent_entries(&mut self) -> Result<Vec<FeedEntry>> {
+        let fetch_started = Utc::now();
+        let mut fresh = Vec::new();
+
+        for url in self.urls.clone() {
+            match self.fetch_feed(&url) {
+                Ok(entries) => fresh.extend(entries),
+                Err(e) => warn!("Failed to fetch feed {}: {}", url, e),
+            }
+        }
+
+        self.persist_seen_ids();
+        self.persist_last_run(fetch_started);
+        self.last_run = fetch_started;
+        Ok(fresh)
+    }
+
+    fn fetch_feed(&mut self, url: &str) -> Result<Vec<FeedEntry>> {
+        let body = self.send_bytes(url)?;
+        let feed = feed_rs::parser::parse(body.as_slice()).context("Failed to parse feed")?;
+        let feed_title = feed
+            .title
+            .map(|t| t.content)
+            .unwrap_or_else(|| url.to_string());
+
+        let mut entries = Vec::new();
+        for entry in feed.entries {
+            let id_hash = hash_id(&entry.id);
+            if self.seen_ids.contains(&id_hash) {
+                continue;
+            }
+            self.remember(id_hash);
+
+            let published = entry.published.or(entry.updated);
+            if let Some(p) = published {
+                if p <= self.last_run {
+                    continue;
+                }
+            }
+
+            entries.push(FeedEntry {
+                feed_title: feed_title.clone(),
+                title: entry
+                    .title
+                    .map(|t| t.content)
+                    .unwrap_or_else(|| "(untitled)".to_string()),
+                link: entry.links.first().map(|l| l.href.clone()),
+                published,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    fn remember(&mut self, id_hash: u64) {
+        self.seen_ids.push_back(id_hash);
+        while self.seen_ids.len() > MAX_SEEN_IDS {
+            self.seen_ids.pop_front();
+        }
+    }
+
+    fn persist_seen_ids(&mut self) {
+        let joined = self
+            .seen_ids
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        if let Err(e) = self.nvs.set_str(SEEN_IDS_KEY, &joined) {
+            warn!("Failed to persist seen feed entry IDs to NVS: {:?}", e);
+        }
+    }
+
+    fn persist_last_run(&mut self, last_run: DateTime<Utc>) {
+        if let Err(e) = self.nvs.set_i64(LAST_RUN_KEY, last_run.timestamp()) {
+            warn!("Failed to persist feed last-run timestamp to NVS: {:?}", e);
+        }
+    }
+
+    /// Fetch the raw feed body, retrying on a dropped connection or a 429/5xx response.
+    fn send_bytes(&self, url: &str) -> Result<Vec<u8>> {
+        retry_with_backoff(&RetryConfig::default(), || {
+            match self.client.get(url).send() {
+                Ok(response) => {
+                    let status = response.status().as_u16();
+                    if is_retryable_status(status) {
+                        return Attempt::Retryable(anyhow::anyhow!(
+                            "Feed fetch returned retryable status {}",
+                            status
+                        ));
+                    }
+                    match response.bytes() {
+                        Ok(bytes) => Attempt::Success(bytes.to_vec()),
+                        Err(e) => Attempt::Fatal(e.into()),
+                    }
+                }
+                Err(e) if e.is_timeout() || e.is_connect() => Attempt::Retryable(e.into()),
+                Err(e) => Attempt::Fatal(e.into()),
+            }
+        })
+    }
+}
+
+fn load_seen_ids(nvs: &EspNvs<NvsDefault>) -> VecDeque<u64> {
+    let mut buf = [0u8; 4096];
+    match nvs.get_str(SEEN_IDS_KEY, &mut buf) {
+        Ok(Some(joined)) => joined.split(',').filter_map(|id| id.parse().ok()).collect(),
+        Ok(None) => VecDeque::new(),
+        Err(e) => {
+            warn!("Failed to read seen feed entry IDs from NVS: {:?}", e);
+            VecDeque::new()
+        }
+    }
+}
+
+/// The persisted last-run timestamp, or "now" if none has been persisted yet, so a
+/// freshly-provisioned device gates out each feed's entire backlog instead of showing it.
+fn load_last_run(nvs: &EspNvs<NvsDefault>) -> DateTime<Utc> {
+    match nvs.get_i64(LAST_RUN_KEY) {
+        Ok(Some(ts)) => DateTime::from_timestamp(ts, 0).unwrap_or_else(Utc::now),
+        Ok(None) => Utc::now(),
+        Err(e) => {
+            warn!("Failed to read feed last-run timestamp from NVS: {:?}", e);
+            Utc::now()
+        }
+    }
+}
+
+fn hash_id(id: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    id.hash(&mut hasher);
+    hasher.finish()
+}
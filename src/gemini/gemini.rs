@@ -7,6 +7,14 @@ use embedded_svc::http::client::Client;
 use embedded_svc::http::Method;
 use esp_idf_svc::http::client::{Configuration, EspHttpConnection};
 
+use super::response_parse::{self, ResponseSchema, ScheduleItem};
+use crate::retry::{is_retryable_status, retry_with_backoff, Attempt, RateLimiter, RetryConfig};
+
+/// Gemini's free tier enforces a modest requests-per-minute cap; default to refilling at
+/// the documented 15 RPM limit, with a burst capacity of a single request.
+const DEFAULT_RATE_LIMIT_CAPACITY: f64 = 1.0;
+const DEFAULT_RATE_LIMIT_PER_SEC: f64 = 15.0 / 60.0;
+
 const GEMINI_API_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta/models";
 const DEFAULT_MODEL: &str = "gemini-3-flash-preview";
 
@@ -19,6 +27,9 @@ My Tasks:
 My Free Time Slots:
     {{timeslots}}
 
+Recent Updates (from subscribed feeds, weave these in where relevant):
+    {{feeds}}
+
 Requirements:
     Decompose: Break each task into a sequence of 'Micro-Steps.' No step should take more than 20 minutes.
     Energy Mapping: Match high-effort brain tasks to my morning slot and physical/administrative tasks to my afternoon slot.
@@ -29,11 +40,22 @@ Requirements:
 pub struct PromptTemplate {
     pub timeslots: Vec<String>,
     pub tasks: Vec<String>,
+    pub feeds: Vec<String>,
 }
 
 #[derive(Debug, Serialize)]
 struct GenerateContentRequest {
     contents: Vec<Content>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "generationConfig")]
+    generation_config: Option<GenerationConfig>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct GenerationConfig {
+    #[serde(rename = "responseMimeType")]
+    response_mime_type: String,
+    #[serde(rename = "responseSchema")]
+    response_schema: serde_json::Value,
 }
 
 #[derive(Debug, Serialize)]
@@ -66,6 +88,8 @@ pub struct GeminiClient {
     api_key: String,
     model: String,
     base_url: String,
+    retry_config: RetryConfig,
+    rate_limiter: RateLimiter,
 }
 
 impl GeminiClient {
@@ -75,6 +99,8 @@ impl GeminiClient {
             api_key: apikey.to_string(),
             model: DEFAULT_MODEL.to_string(),
             base_url: GEMINI_API_BASE_URL.to_string(),
+            retry_config: RetryConfig::default(),
+            rate_limiter: RateLimiter::new(DEFAULT_RATE_LIMIT_CAPACITY, DEFAULT_RATE_LIMIT_PER_SEC),
         }
     }
 
@@ -100,10 +126,61 @@ impl GeminiClient {
         self
     }
 
+    /// Configure the retry-with-backoff behavior used for every request.
+    pub fn with_retry(mut self, max_retries: u32, base_delay: Duration) -> Self {
+        self.retry_config.max_retries = max_retries;
+        self.retry_config.base_delay = base_delay;
+        self
+    }
+
+    /// Configure the client-side token-bucket rate limit (capacity, refill tokens/sec).
+    pub fn with_rate_limit(mut self, capacity: f64, refill_per_sec: f64) -> Self {
+        self.rate_limiter = RateLimiter::new(capacity, refill_per_sec);
+        self
+    }
+
+    /// Plain-text generation: no response schema, Gemini replies in prose.
     pub fn generate_content(&mut self, prompt: &str) -> Result<String> {
+        self.request(prompt, None)
+    }
+
+    /// Ask Gemini for a schedule, constraining its reply to the `ScheduleItem` JSON
+    /// schema via `generationConfig.responseSchema` instead of hoping it follows the
+    /// `--__--` prose format. Falls back to the regex scrape if JSON parsing fails.
+    pub fn generate_schedule(&mut self, prompt: &str) -> Result<Vec<ScheduleItem>> {
+        let text = self.request(
+            prompt,
+            Some(GenerationConfig {
+                response_mime_type: "application/json".to_string(),
+                response_schema: ScheduleItem::response_schema(),
+            }),
+        )?;
+
+        info!("Gemini Plan: {}", text);
+        response_parse::extract_schedule(&text)
+    }
 
+    fn request(&mut self, prompt: &str, generation_config: Option<GenerationConfig>) -> Result<String> {
+        let retry_config = self.retry_config.clone();
+
+        retry_with_backoff(&retry_config, || {
+            self.rate_limiter.acquire();
+            self.attempt_request(prompt, &generation_config)
+        })
+    }
+
+    /// A single request/response round trip, classified into `Attempt` so the retry
+    /// loop can tell a transient failure (worth retrying) from a fatal one.
+    fn attempt_request(
+        &mut self,
+        prompt: &str,
+        generation_config: &Option<GenerationConfig>,
+    ) -> Attempt<String> {
         if self.client.is_none() {
-            self.client = Some(Self::create_client()?);
+            match Self::create_client() {
+                Ok(client) => self.client = Some(client),
+                Err(e) => return Attempt::Retryable(e),
+            }
         }
         let local_client = self.client.as_mut().unwrap();
 
@@ -118,8 +195,12 @@ impl GeminiClient {
                     text: prompt.to_string(),
                 }],
             }],
+            generation_config: generation_config.clone(),
+        };
+        let body_str = match serde_json::to_string(&request_body) {
+            Ok(body) => body,
+            Err(e) => return Attempt::Fatal(e.into()),
         };
-        let body_str = serde_json::to_string(&request_body)?;
         let content_length = body_str.len().to_string();
 
         let auth_str = format!("x-goog-api-key:{}", self.api_key);
@@ -133,18 +214,26 @@ impl GeminiClient {
 
         let mut response = match local_client.request(Method::Post, &url, &headers) {
             Ok(mut req) => {
-                req.write(&body_str.as_bytes()).context("Failed to get request writer")?;
-
-                req.submit().context("Failed to submit request")?
-
-            },
+                if let Err(e) = req.write(&body_str.as_bytes()) {
+                    self.client = None;
+                    return Attempt::Retryable(anyhow::anyhow!("Failed to write request body: {:?}", e));
+                }
+                match req.submit() {
+                    Ok(response) => response,
+                    Err(e) => {
+                        self.client = None;
+                        return Attempt::Retryable(anyhow::anyhow!("Failed to submit request: {:?}", e));
+                    }
+                }
+            }
             Err(e) => {
-                info!("Failed to create request: {:?}", e);
-                return Err(anyhow::anyhow!("Failed to create request: {:?}", e));
+                self.client = None;
+                return Attempt::Retryable(anyhow::anyhow!("Failed to create request: {:?}", e));
             }
         };
 
-        info!("Response status: {}", response.status());
+        let status = response.status();
+        info!("Response status: {}", status);
 
         let mut buf = [0u8; 10240];
         let mut response_body = Vec::<u8>::new();
@@ -156,22 +245,36 @@ impl GeminiClient {
                     response_body.extend_from_slice(&buf[..len]);
                 }
                 Err(e) => {
-                    info!("Error reading response: {:?}", e);
-                    return Err(anyhow::anyhow!("Error reading response: {:?}", e));
+                    self.client = None;
+                    return Attempt::Retryable(anyhow::anyhow!("Error reading response: {:?}", e));
                 }
             }
         }
 
         self.client = None;
 
-        let response: GenerateContentResponse = serde_json::from_slice(&response_body)
-            .context("Failed to parse JSON response")?;
+        if is_retryable_status(status) {
+            return Attempt::Retryable(anyhow::anyhow!("Gemini returned retryable status {}", status));
+        }
+        if status >= 400 {
+            return Attempt::Fatal(anyhow::anyhow!("Gemini returned status {}", status));
+        }
 
-        Ok(response
+        let response: GenerateContentResponse = match serde_json::from_slice(&response_body)
+            .context("Failed to parse JSON response")
+        {
+            Ok(response) => response,
+            Err(e) => return Attempt::Fatal(e),
+        };
+
+        match response
             .candidates
             .first()
             .and_then(|c| c.content.parts.first())
             .map(|p| p.text.clone())
-            .ok_or_else(|| anyhow::anyhow!("No text in response"))?)
+        {
+            Some(text) => Attempt::Success(text),
+            None => Attempt::Fatal(anyhow::anyhow!("No text in response")),
+        }
     }
 }
\ No newline at end of file
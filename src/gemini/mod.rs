@@ -2,4 +2,4 @@ mod gemini;
 mod response_parse;
 
 pub use gemini::{GeminiClient, DEFAULT_PROMPT, PromptTemplate};
-pub use response_parse::{extract_schedule, ScheduleItem};
\ No newline at end of file
+pub use response_parse::{extract_schedule, ResponseSchema, ScheduleItem};
\ No newline at end of file
@@ -1,7 +1,16 @@
 use regex::Regex;
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 
-#[derive(Debug, Clone, PartialEq)]
+/// Implemented by schedule types that can describe their own shape as a Gemini
+/// `responseSchema`, so new fields automatically flow into the structured-output request.
+/// Doubles as the schema source for the RPC server's `/rpc.json` discovery document.
+pub trait ResponseSchema {
+    fn response_schema() -> serde_json::Value;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ScheduleItem {
     pub time_start: String,
     pub time_end: String,
@@ -18,7 +27,47 @@ impl ScheduleItem {
     }
 }
 
+impl ResponseSchema for ScheduleItem {
+    fn response_schema() -> serde_json::Value {
+        json!({
+            "type": "ARRAY",
+            "items": {
+                "type": "OBJECT",
+                "properties": {
+                    "time_start": { "type": "STRING", "pattern": "^\\d{1,2}:\\d{2}$" },
+                    "time_end": { "type": "STRING", "pattern": "^\\d{1,2}:\\d{2}$" },
+                    "task": { "type": "STRING" }
+                },
+                "required": ["time_start", "time_end", "task"]
+            }
+        })
+    }
+}
+
+/// Parse a schedule out of a Gemini response. Tries the structured JSON path first
+/// (tolerating markdown code fences around the array), and falls back to the legacy
+/// `--__-- HH:MM - HH:MM: task` regex scrape if the model didn't return valid JSON.
 pub fn extract_schedule(input: &str) -> Result<Vec<ScheduleItem>> {
+    if let Some(schedule) = parse_json_schedule(input) {
+        return Ok(schedule);
+    }
+
+    extract_schedule_regex(input)
+}
+
+fn parse_json_schedule(input: &str) -> Option<Vec<ScheduleItem>> {
+    serde_json::from_str(strip_markdown_fence(input.trim())).ok()
+}
+
+fn strip_markdown_fence(s: &str) -> &str {
+    let s = s
+        .strip_prefix("```json")
+        .or_else(|| s.strip_prefix("```"))
+        .unwrap_or(s);
+    s.strip_suffix("```").unwrap_or(s).trim()
+}
+
+fn extract_schedule_regex(input: &str) -> Result<Vec<ScheduleItem>> {
     let pattern = Regex::new(r"--__-- (\d{1,2}:\d{2}) - (\d{1,2}:\d{2}): (.+)").unwrap();
     let mut schedule = Vec::new();
 
@@ -33,4 +82,38 @@ pub fn extract_schedule(input: &str) -> Result<Vec<ScheduleItem>> {
     }
 
     Ok(schedule)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_json_fence() {
+        assert_eq!(strip_markdown_fence("```json\n[1,2,3]\n```"), "[1,2,3]");
+    }
+
+    #[test]
+    fn strips_bare_fence() {
+        assert_eq!(strip_markdown_fence("```\n[1,2,3]\n```"), "[1,2,3]");
+    }
+
+    #[test]
+    fn leaves_unfenced_input_alone() {
+        assert_eq!(strip_markdown_fence("[1,2,3]"), "[1,2,3]");
+    }
+
+    #[test]
+    fn parses_fenced_json_schedule() {
+        let input = "```json\n[{\"time_start\":\"9:00\",\"time_end\":\"10:00\",\"task\":\"standup\"}]\n```";
+        let schedule = extract_schedule(input).unwrap();
+        assert_eq!(schedule, vec![ScheduleItem::new("9:00", "10:00", "standup")]);
+    }
+
+    #[test]
+    fn falls_back_to_regex_scrape_when_not_json() {
+        let input = "some preamble\n--__-- 9:00 - 10:00: standup\nsome trailer";
+        let schedule = extract_schedule(input).unwrap();
+        assert_eq!(schedule, vec![ScheduleItem::new("9:00", "10:00", "standup")]);
+    }
 }
\ No newline at end of file
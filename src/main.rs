@@ -4,16 +4,27 @@
 //! to help prioritize your daily work.
 
 mod calendar;
+mod feeds;
 mod notion;
+mod poller;
+mod retry;
+mod rpc;
+mod sensor;
 mod waveshare;
 mod wifi;
 mod gemini;
 
-use chrono::Local;
-use esp_idf_hal::{gpio::Pins, spi::SPI2};
+use chrono::{Local, NaiveTime};
+use embedded_graphics::mono_font::{ascii::FONT_6X9, MonoTextStyleBuilder};
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::text::Text;
+use esp_idf_hal::gpio::{Gpio9, Gpio10, Gpio11, Gpio12, Gpio13, Gpio14, Gpio46};
+use esp_idf_hal::spi::SPI2;
 use log::info;
 use anyhow::Result;
 use minijinja::{Environment, context};
+use std::collections::VecDeque;
 
 use esp_idf_sys as _;
 use esp_idf_svc::log::EspLogger;
@@ -21,17 +32,44 @@ use esp_idf_svc::eventloop::EspSystemEventLoop;
 use esp_idf_svc::nvs::EspDefaultNvsPartition;
 use esp_idf_svc::sntp::EspSntp;
 use esp_idf_sys::tzset;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use wifi::wifi_up;
 use waveshare::{Epd, FrameBuffer};
 use gemini::{ScheduleItem, PromptTemplate};
-use crate::{calendar::FreeSlot, gemini::DEFAULT_PROMPT};
+use rpc::{FreeSlotSummary, RpcState};
+use sensor::{Dht22, Reading};
+use crate::{calendar::{BusyPeriod, FreeSlot}, gemini::DEFAULT_PROMPT};
 
 // Display Color Values
 const BLACK: u8 = 0x00;
 const WHITE: u8 = 0x01;
 
+/// How often the poller re-runs the fetch -> plan -> display pipeline.
+const POLL_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+// Agenda timeline layout, in pixels on the 400x300 FrameBuffer.
+const AGENDA_LABEL_WIDTH: u32 = 60;
+const AGENDA_BAR_WIDTH: u32 = 330;
+const AGENDA_HEIGHT: u32 = 150;
+
+// Sensor history graph layout, in pixels.
+const SENSOR_GRAPH_WIDTH: u32 = 360;
+const SENSOR_GRAPH_HEIGHT: u32 = 50;
+
+// Live clock widget layout, in pixels. `x`/`w` must be byte-aligned (multiples of 8)
+// since the partial-update command windows are specified in 8-pixel bytes.
+const CLOCK_X: u32 = 328;
+const CLOCK_Y: u32 = 2;
+const CLOCK_WIDTH: u32 = 64;
+const CLOCK_HEIGHT: u32 = 10;
+
+/// Row the agenda timeline starts at, leaving room above it for the clock widget.
+const AGENDA_START_ROW: u32 = 14;
+
 fn sync_time() -> anyhow::Result<()> {
     log::info!("Initializing SNTP...");
 
@@ -57,14 +95,14 @@ fn sync_time() -> anyhow::Result<()> {
 }
 
 /// Fetch and display Google Calendar events and free time slots
-fn fetch_calendar_events() -> Result<Vec<FreeSlot>> {
+fn fetch_calendar_events(
+    token_store: &mut calendar::TokenStore,
+    event_cache: &mut calendar::EventCache,
+) -> Result<(Vec<BusyPeriod>, Vec<FreeSlot>)> {
     info!("--- Google Calendar ---");
 
-    let access_token = calendar::get_credentials()?;
-
-    let events = calendar::get_todays_events(&access_token)?;
-
-    let (busy_periods, free_slots) = calendar::calculate_free_time(&events);
+    let (_events, busy_periods, free_slots) =
+        calendar::get_todays_events_with_cache(token_store, event_cache)?;
 
     info!(
         "Working hours: {}:00 - {}:00",
@@ -104,11 +142,46 @@ fn fetch_calendar_events() -> Result<Vec<FreeSlot>> {
     } else {
         info!("  No free time available during working hours!");
     }
-    Ok(free_slots)
+    Ok((busy_periods, free_slots))
+}
+
+/// Fetch feed entries published since the last run
+fn fetch_feed_entries(feed_source: &mut feeds::FeedSource) -> Result<Vec<feeds::FeedEntry>> {
+    info!("--- Feeds ---");
+
+    let entries = feed_source.fetch_recent_entries()?;
+    if entries.is_empty() {
+        info!("No new feed entries since last run");
+    } else {
+        for entry in &entries {
+            info!(
+                "  [{}] {} ({})",
+                entry.feed_title,
+                entry.title,
+                entry
+                    .published
+                    .map(|p| p.to_rfc3339())
+                    .unwrap_or_else(|| "unknown time".to_string())
+            );
+        }
+    }
+    Ok(entries)
+}
+
+/// Take a fresh reading from the environmental sensor
+fn fetch_sensor_reading(sensor: &mut Dht22) -> Result<Reading> {
+    info!("--- Environmental Sensor ---");
+
+    let reading = sensor.read()?;
+    info!(
+        "Temperature: {:.1}C, Humidity: {:.1}%",
+        reading.temperature_c, reading.humidity_pct
+    );
+    Ok(reading)
 }
 
 /// Fetch and display active Notion tasks
-fn fetch_notion_tasks() -> Result<Vec<String>>{
+fn fetch_notion_tasks() -> Result<Vec<notion::Task>> {
     info!("--- Notion Tasks ---");
 
     let api_key = match option_env!("NOTION_API_KEY") {
@@ -124,7 +197,8 @@ fn fetch_notion_tasks() -> Result<Vec<String>>{
 
     let datasource_response = notion_client.query_datasource(notion::SOURCE_ID, None)?;
 
-    Ok(notion::extract_active_tasks(&datasource_response))
+    notion::extract_active_tasks(&datasource_response)
+        .map_err(|e| anyhow::anyhow!("Failed to extract Notion tasks: {}", e))
 }
 
 fn ask_gemini(prompt: &str) -> Result<Vec<ScheduleItem>> {
@@ -140,21 +214,27 @@ fn ask_gemini(prompt: &str) -> Result<Vec<ScheduleItem>> {
 
     let mut gemini_client = gemini::GeminiClient::new(api_key);
 
-    let response = gemini_client.generate_content(prompt)?;
-
-    info!("Gemini Plan: {}", response);
-    gemini::extract_schedule(&response)
+    gemini_client.generate_schedule(prompt)
 }
 
-fn set_up_display(esp_peripheral_pins: Pins, spi: SPI2) -> Result<Epd<'static>> {    
+fn set_up_display(
+    sck: Gpio12,
+    mosi: Gpio11,
+    miso: Gpio46,
+    cs: Gpio10,
+    dc: Gpio9,
+    reset: Gpio13,
+    busy: Gpio14,
+    spi: SPI2,
+) -> Result<Epd<'static>> {
         Ok(Epd::new_explicit(
-            esp_peripheral_pins.gpio12,   // any pin for sck
-            esp_peripheral_pins.gpio11,   // any pin for mosi
-            esp_peripheral_pins.gpio46,   // any pin for miso
-            esp_peripheral_pins.gpio10,   // any pin for cs
-            esp_peripheral_pins.gpio9,   // any pin for dc
-            esp_peripheral_pins.gpio13,  // any pin for reset
-            esp_peripheral_pins.gpio14,  // any pin for busy
+            sck,   // any pin for sck
+            mosi,   // any pin for mosi
+            miso,   // any pin for miso
+            cs,   // any pin for cs
+            dc,   // any pin for dc
+            reset,  // any pin for reset
+            busy,  // any pin for busy
             spi,
         ))
 }
@@ -208,6 +288,204 @@ fn create_free_time_display(fb: &mut FrameBuffer, free_slots: &[FreeSlot], start
     Ok(y)
 }
 
+/// Draw a vertical day timeline from `WORK_START_HOUR` to `WORK_END_HOUR`, with hour
+/// gridlines, filled blocks for `BusyPeriod`s (title drawn inside), and outlined blocks
+/// for `FreeSlot`s (annotated with their duration).
+fn create_agenda_display(
+    fb: &mut FrameBuffer,
+    busy_periods: &[BusyPeriod],
+    free_slots: &[FreeSlot],
+    start_row: u32,
+) -> Result<u32> {
+    info!("Displaying today's agenda on the screen...");
+
+    let today = Local::now().date_naive();
+    let work_start = today.and_time(NaiveTime::from_hms_opt(calendar::WORK_START_HOUR, 0, 0).unwrap());
+    let total_minutes = ((calendar::WORK_END_HOUR - calendar::WORK_START_HOUR) * 60) as i64;
+
+    let row_for = |time: chrono::NaiveDateTime| -> u32 {
+        let minutes_since_start = (time - work_start).num_minutes().clamp(0, total_minutes);
+        start_row + (minutes_since_start as u32 * AGENDA_HEIGHT) / total_minutes as u32
+    };
+
+    for hour in calendar::WORK_START_HOUR..=calendar::WORK_END_HOUR {
+        let y = row_for(today.and_time(NaiveTime::from_hms_opt(hour, 0, 0).unwrap()));
+        fb.hline(AGENDA_LABEL_WIDTH, y, AGENDA_BAR_WIDTH, BLACK);
+        fb.text(&format!("{:02}:00", hour), 4, y, BLACK);
+    }
+
+    for period in busy_periods {
+        let y_start = row_for(period.start);
+        let y_end = row_for(period.end).max(y_start + 1);
+        let height = y_end - y_start;
+        fb.fill_rect(AGENDA_LABEL_WIDTH, y_start, AGENDA_BAR_WIDTH, height, BLACK);
+        if height >= 10 {
+            let max_chars = (AGENDA_BAR_WIDTH as usize - 8) / 8;
+            let title: String = period.title.chars().take(max_chars).collect();
+            fb.text(&title, AGENDA_LABEL_WIDTH + 4, y_start + 2, WHITE);
+        }
+    }
+
+    for slot in free_slots {
+        let y_start = row_for(slot.start);
+        let y_end = row_for(slot.end).max(y_start + 1);
+        let height = y_end - y_start;
+        fb.rect(AGENDA_LABEL_WIDTH, y_start, AGENDA_BAR_WIDTH, height, BLACK);
+        if height >= 10 {
+            let label = calendar::format_duration(slot.end - slot.start);
+            fb.text(&label, AGENDA_LABEL_WIDTH + 4, y_start + 2, BLACK);
+        }
+    }
+
+    Ok(start_row + AGENDA_HEIGHT)
+}
+
+/// Row of the current time within the agenda timeline drawn by `create_agenda_display`,
+/// for the "now" marker. Mirrors that function's `row_for` so the marker lines up with
+/// the hour gridlines and event blocks.
+fn agenda_now_row(start_row: u32) -> u32 {
+    let today = Local::now().date_naive();
+    let work_start = today.and_time(NaiveTime::from_hms_opt(calendar::WORK_START_HOUR, 0, 0).unwrap());
+    let total_minutes = ((calendar::WORK_END_HOUR - calendar::WORK_START_HOUR) * 60) as i64;
+    let minutes_since_start = (Local::now().naive_local() - work_start)
+        .num_minutes()
+        .clamp(0, total_minutes);
+    start_row + (minutes_since_start as u32 * AGENDA_HEIGHT) / total_minutes as u32
+}
+
+/// Draw the agenda's "now" marker: a single gridline-style row at `row`, the same way
+/// `create_agenda_display` draws hour gridlines.
+fn draw_now_marker(fb: &mut FrameBuffer, row: u32) {
+    fb.hline(AGENDA_LABEL_WIDTH, row, AGENDA_BAR_WIDTH, BLACK);
+}
+
+/// Redraw the live clock widget with the current time at `(x, y)` in `fb`. Clears its
+/// background first since the text style has no background color and only sets the
+/// pixels its glyphs need, so a shorter string wouldn't otherwise erase a longer one
+/// drawn there before.
+fn render_clock(fb: &mut FrameBuffer, x: u32, y: u32) {
+    fb.fill_rect(x, y, CLOCK_WIDTH, CLOCK_HEIGHT, WHITE);
+
+    let style = MonoTextStyleBuilder::new()
+        .font(&FONT_6X9)
+        .text_color(BinaryColor::On)
+        .build();
+    let text = Local::now().format("%H:%M").to_string();
+    Text::new(&text, Point::new((x + 4) as i32, (y + 8) as i32), style)
+        .draw(fb)
+        .expect("drawing into a FrameBuffer is infallible");
+}
+
+/// Push a partial update for the live clock and the agenda's "now" marker. The clock is
+/// drawn fresh into a small scratch buffer; the marker is drawn over a strip cropped out
+/// of `latest_frame` (the last fully-rendered frame, without these overlays), so neither
+/// needs a copy of the whole screen. `last_marker_row` tracks the marker's previous
+/// position so its old row is covered by the same partial-update window.
+fn update_live_regions(
+    epd: &mut Epd<'_>,
+    latest_frame: &Option<FrameBuffer>,
+    last_marker_row: &mut Option<u32>,
+) {
+    let clean = match latest_frame {
+        Some(clean) => clean,
+        None => return,
+    };
+
+    let mut clock_frame = FrameBuffer::new(CLOCK_WIDTH, CLOCK_HEIGHT);
+    render_clock(&mut clock_frame, 0, 0);
+    epd.display_partial_region(CLOCK_X, CLOCK_Y, CLOCK_WIDTH, CLOCK_HEIGHT, clock_frame.buffer());
+
+    let new_row = agenda_now_row(AGENDA_START_ROW);
+    let prev_row = last_marker_row.replace(new_row).unwrap_or(new_row);
+    let band_top = new_row.min(prev_row).saturating_sub(2);
+    let band_height = (new_row.max(prev_row) + 2).saturating_sub(band_top);
+
+    let width = epd.width();
+    let band_bytes = clean.crop_bytes(0, band_top, width, band_height);
+    let mut marker_frame = FrameBuffer::from_bytes(width, band_height, band_bytes);
+    draw_now_marker(&mut marker_frame, new_row - band_top);
+    epd.display_partial_region(0, band_top, width, band_height, marker_frame.buffer());
+}
+
+fn create_feed_display(fb: &mut FrameBuffer, entries: &[feeds::FeedEntry], start_row: u32) -> Result<u32> {
+    info!("Displaying feed updates on the screen...");
+    let headline = "Recent Updates".to_string();
+    let mut y = start_row;
+    fb.text(&headline, 30, y, BLACK);
+    for entry in entries {
+        y += 10;
+        fb.text(&format!("{}: {}", entry.feed_title, entry.title), 10, y, BLACK);
+    }
+
+    Ok(y)
+}
+
+/// Plot the rolling temperature and humidity history below the agenda as two stacked
+/// line graphs, each labelled with its min/max over the window using `FrameBuffer::line`.
+fn create_sensor_graph_display(
+    fb: &mut FrameBuffer,
+    history: &VecDeque<Reading>,
+    start_row: u32,
+) -> Result<u32> {
+    info!("Displaying sensor history on the screen...");
+
+    if history.is_empty() {
+        fb.text("No sensor readings yet", 10, start_row + 10, BLACK);
+        return Ok(start_row + 20);
+    }
+
+    let mut y = plot_series(
+        fb,
+        "Temp (C)",
+        history.iter().map(|r| r.temperature_c),
+        start_row,
+    )?;
+    y = plot_series(
+        fb,
+        "Humidity (%)",
+        history.iter().map(|r| r.humidity_pct),
+        y + 10,
+    )?;
+    Ok(y)
+}
+
+/// Draw a single labelled line graph of `values` starting at `start_row`, scaled to fit
+/// `SENSOR_GRAPH_WIDTH` x `SENSOR_GRAPH_HEIGHT`. Returns the graph's bottom row.
+fn plot_series(
+    fb: &mut FrameBuffer,
+    label: &str,
+    values: impl Iterator<Item = f32>,
+    start_row: u32,
+) -> Result<u32> {
+    let values: Vec<f32> = values.collect();
+    let min = values.iter().cloned().fold(f32::INFINITY, f32::min);
+    let max = values.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+    let range = (max - min).max(1.0);
+
+    fb.text(
+        &format!("{} ({:.1} - {:.1})", label, min, max),
+        10,
+        start_row,
+        BLACK,
+    );
+    let graph_top = start_row + 10;
+
+    let x_for = |i: usize| -> i32 {
+        let steps = (values.len() - 1).max(1) as u32;
+        (10 + (i as u32 * SENSOR_GRAPH_WIDTH) / steps) as i32
+    };
+    let y_for = |v: f32| -> i32 {
+        let offset = (((v - min) / range) * SENSOR_GRAPH_HEIGHT as f32) as u32;
+        (graph_top + SENSOR_GRAPH_HEIGHT - offset) as i32
+    };
+
+    for (i, pair) in values.windows(2).enumerate() {
+        fb.line(x_for(i), y_for(pair[0]), x_for(i + 1), y_for(pair[1]), BLACK);
+    }
+
+    Ok(graph_top + SENSOR_GRAPH_HEIGHT)
+}
+
 fn main() -> Result<()> {
     EspLogger::initialize_default();
 
@@ -220,7 +498,7 @@ fn main() -> Result<()> {
     let sys_loop = EspSystemEventLoop::take()?;
     let nvs = EspDefaultNvsPartition::take()?;
 
-    let session_wifi = wifi_up(system_peripherals.modem, sys_loop, nvs)?;
+    let session_wifi = wifi_up(system_peripherals.modem, sys_loop, nvs.clone())?;
     info!(
         "DHCP server assigned IP address: {:?}",
         session_wifi.wifi().sta_netif().get_ip_info()?
@@ -228,61 +506,165 @@ fn main() -> Result<()> {
 
     sync_time()?;
 
-    let free_slots = fetch_calendar_events()?;
-    info!("\n{}", "-".repeat(50));
-
-    let tasks = fetch_notion_tasks()?;
-    info!("Active tasks (To Do / Doing):");
-    for task in &tasks {
-        info!("  - {}", task);
-    }
-    info!("\nTotal tasks: {}", tasks.len());
-
-    info!("\n{}", "=".repeat(50));
-
-    let prompt_data = PromptTemplate {
-        timeslots: free_slots.iter().map(|slot| format!("\t[Time: {} - {}\n]", slot.start.format("%H:%M"), slot.end.format("%H:%M"))).collect(),
-        tasks: tasks.iter().map(|task| format!("\t[Task: {}\n]", task)).collect(),
-    };
-    let rendered = Environment::new().render_str(DEFAULT_PROMPT, context! {
-        timeslots => prompt_data.timeslots,
-        tasks => prompt_data.tasks
-    })?;
-    let todays_tasks = ask_gemini(&rendered)?;
-    info!("\n Gemini says: \n {:?}", todays_tasks);
-
-    info!("Daily planning complete!");
-
-    let mut epd = set_up_display(system_peripherals.pins, system_peripherals.spi2)?;
-
+    let mut token_store = calendar::TokenStore::new(nvs.clone())?;
+    let mut event_cache = calendar::EventCache::new(nvs.clone())?;
+
+    let feed_urls: Vec<String> = option_env!("FEED_URLS")
+        .map(|urls| {
+            urls.split(',')
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut feed_source = feeds::FeedSource::new(nvs.clone(), feed_urls)?;
+    let latest_feed_entries: Rc<RefCell<Vec<feeds::FeedEntry>>> = Rc::new(RefCell::new(Vec::new()));
+    let latest_agenda: Rc<RefCell<(Vec<BusyPeriod>, Vec<FreeSlot>)>> =
+        Rc::new(RefCell::new((Vec::new(), Vec::new())));
+    let latest_sensor_history: Rc<RefCell<VecDeque<Reading>>> = Rc::new(RefCell::new(VecDeque::new()));
+
+    let pins = system_peripherals.pins;
+    let mut epd = set_up_display(
+        pins.gpio12,
+        pins.gpio11,
+        pins.gpio46,
+        pins.gpio10,
+        pins.gpio9,
+        pins.gpio13,
+        pins.gpio14,
+        system_peripherals.spi2,
+    )?;
     info!("Resetting the screen...");
     epd.init();
     epd.clear();
-
-    // Create framebuffer
-    let mut fb = FrameBuffer::new(epd.width(), epd.height());
-    fb.fill(WHITE);
-    info!("Created buffer of size: {} bytes", fb.buffer().len());
-
-    let end_row = display_daily_plan(&mut fb, &todays_tasks, 0)?;
-    fb.hline(0, end_row + 20, 200, BLACK);
-
-    info!("Writing FrameBuffer to display");
-    epd.display(fb.buffer());
-
-    // fb.pixel(30, 10, BLACK);
-    // fb.hline(30, 30, 10, BLACK);
-    // fb.vline(30, 50, 10, BLACK);
-    // fb.line(30, 70, 40, 80, BLACK);
-    // fb.rect(30, 90, 10, 10, BLACK);
-    // fb.fill_rect(30, 110, 10, 10, BLACK);
-    // for row in 0..36 {
-    //     let row_str = row.to_string();
-    //     fb.text(&row_str, 0, row * 8, BLACK);
-    // }
-    // fb.text("Line 36", 0, 288, BLACK);
-
-    epd.sleep();
-
-    Ok(())
+    let epd = Rc::new(RefCell::new(epd));
+
+    let mut sensor = Dht22::new(pins.gpio4)?;
+
+    let mut poller = poller::Poller::new(nvs, POLL_INTERVAL)?;
+
+    let rpc_state = Arc::new(Mutex::new(RpcState::default()));
+    let _rpc_server = rpc::start(rpc_state.clone(), poller.refresh_handle())?;
+    info!("RPC server listening");
+
+    // The last fully-rendered frame (without the clock/"now" marker overlays) and the
+    // marker's last drawn row, so idle ticks can push small partial updates without
+    // redoing the full fetch -> plan -> render pipeline.
+    let latest_frame: Rc<RefCell<Option<FrameBuffer>>> = Rc::new(RefCell::new(None));
+    let last_marker_row: Rc<RefCell<Option<u32>>> = Rc::new(RefCell::new(None));
+
+    let latest_feed_entries_for_render = latest_feed_entries.clone();
+    let latest_agenda_for_render = latest_agenda.clone();
+    let latest_sensor_history_for_render = latest_sensor_history.clone();
+    let epd_for_render = epd.clone();
+    let latest_frame_for_render = latest_frame.clone();
+    let last_marker_row_for_render = last_marker_row.clone();
+
+    let epd_for_idle = epd.clone();
+    let latest_frame_for_idle = latest_frame.clone();
+    let last_marker_row_for_idle = last_marker_row.clone();
+
+    poller.run_forever(
+        || -> Result<Vec<ScheduleItem>> {
+            let (busy_periods, free_slots) = fetch_calendar_events(&mut token_store, &mut event_cache)?;
+            rpc_state.lock().unwrap().free_slots =
+                free_slots.iter().map(FreeSlotSummary::from).collect();
+            *latest_agenda.borrow_mut() = (busy_periods, free_slots.clone());
+            info!("\n{}", "-".repeat(50));
+
+            let tasks = fetch_notion_tasks()?;
+            info!("Active tasks (To Do / Doing):");
+            for task in &tasks {
+                info!(
+                    "  - {} (status: {}, due: {}, priority: {})",
+                    task.name,
+                    task.status,
+                    task.due.map(|d| d.to_string()).unwrap_or_else(|| "none".to_string()),
+                    task.priority.as_deref().unwrap_or("none")
+                );
+            }
+            info!("\nTotal tasks: {}", tasks.len());
+
+            let feed_entries = fetch_feed_entries(&mut feed_source)?;
+            let feed_lines: Vec<String> = feed_entries
+                .iter()
+                .map(|entry| format!("\t[{}: {}\n]", entry.feed_title, entry.title))
+                .collect();
+            *latest_feed_entries.borrow_mut() = feed_entries;
+
+            fetch_sensor_reading(&mut sensor)?;
+            *latest_sensor_history.borrow_mut() = sensor.history().clone();
+
+            info!("\n{}", "=".repeat(50));
+
+            let prompt_data = PromptTemplate {
+                timeslots: free_slots.iter().map(|slot| format!("\t[Time: {} - {}\n]", slot.start.format("%H:%M"), slot.end.format("%H:%M"))).collect(),
+                tasks: tasks
+                    .iter()
+                    .map(|task| {
+                        format!(
+                            "\t[Task: {} (status: {}, due: {}, priority: {})\n]",
+                            task.name,
+                            task.status,
+                            task.due.map(|d| d.to_string()).unwrap_or_else(|| "none".to_string()),
+                            task.priority.as_deref().unwrap_or("none")
+                        )
+                    })
+                    .collect(),
+                feeds: feed_lines,
+            };
+            let rendered = Environment::new().render_str(DEFAULT_PROMPT, context! {
+                timeslots => prompt_data.timeslots,
+                tasks => prompt_data.tasks,
+                feeds => prompt_data.feeds
+            })?;
+            let todays_tasks = ask_gemini(&rendered)?;
+            info!("\n Gemini says: \n {:?}", todays_tasks);
+            rpc_state.lock().unwrap().schedule = todays_tasks.clone();
+
+            info!("Daily planning complete!");
+            Ok(todays_tasks)
+        },
+        |todays_tasks| -> Result<()> {
+            let (width, height) = {
+                let epd = epd_for_render.borrow();
+                (epd.width(), epd.height())
+            };
+            let mut fb = FrameBuffer::new(width, height);
+            fb.fill(WHITE);
+            info!("Created buffer of size: {} bytes", fb.buffer().len());
+
+            let agenda = latest_agenda_for_render.borrow();
+            let end_row = create_agenda_display(&mut fb, &agenda.0, &agenda.1, AGENDA_START_ROW)?;
+            let end_row = display_daily_plan(&mut fb, todays_tasks, end_row + 10)?;
+            let end_row = create_feed_display(&mut fb, &latest_feed_entries_for_render.borrow(), end_row + 10)?;
+            let end_row = create_sensor_graph_display(&mut fb, &latest_sensor_history_for_render.borrow(), end_row + 10)?;
+            fb.hline(0, end_row + 20, 200, BLACK);
+
+            *latest_frame_for_render.borrow_mut() = Some(fb.clone());
+
+            render_clock(&mut fb, CLOCK_X, CLOCK_Y);
+            let now_row = agenda_now_row(AGENDA_START_ROW);
+            draw_now_marker(&mut fb, now_row);
+            *last_marker_row_for_render.borrow_mut() = Some(now_row);
+
+            info!("Writing FrameBuffer to display");
+            epd_for_render.borrow_mut().display(fb.buffer());
+            Ok(())
+        },
+        |status, retry_count, last_run| {
+            let mut rpc_state = rpc_state.lock().unwrap();
+            rpc_state.status = Some(status);
+            rpc_state.retry_count = retry_count;
+            rpc_state.last_run = last_run;
+        },
+        move || {
+            update_live_regions(
+                &mut epd_for_idle.borrow_mut(),
+                &latest_frame_for_idle.borrow(),
+                &mut last_marker_row_for_idle.borrow_mut(),
+            );
+        },
+    )
 }
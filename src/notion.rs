@@ -1,16 +1,21 @@
 //! Notion API Client Module
 //!
-//! Provides async access to the Notion API for querying databases, pages, and datasources.
+//! Provides blocking access to the Notion API for querying databases, pages, and datasources.
 
+use chrono::NaiveDate;
+use log::warn;
 use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
 use serde_json::{json, Value};
+use std::fmt;
+
+use crate::retry::{is_retryable_status, retry_with_backoff, Attempt, RetryConfig};
 
 pub const NOTION_API_VERSION: &str = "2025-09-03";
 pub const NOTION_BASE_URL: &str = "https://api.notion.com/v1";
 pub const SOURCE_ID: &str = "93f885016df945c8ade315557cefd023";
 
 pub struct NotionClient {
-    client: reqwest::Client,
+    client: reqwest::blocking::Client,
     base_url: String,
 }
 
@@ -27,7 +32,7 @@ impl NotionClient {
             HeaderValue::from_static(NOTION_API_VERSION),
         );
 
-        let client = reqwest::Client::builder()
+        let client = reqwest::blocking::Client::builder()
             .default_headers(headers)
             .build()
             .unwrap();
@@ -38,15 +43,39 @@ impl NotionClient {
         }
     }
 
+    /// Send a request built fresh on each attempt, retrying on a dropped connection
+    /// or a 429/5xx response.
+    fn send_json(
+        &self,
+        build: impl Fn(&reqwest::blocking::Client) -> reqwest::blocking::RequestBuilder,
+    ) -> anyhow::Result<Value> {
+        retry_with_backoff(&RetryConfig::default(), || match build(&self.client).send() {
+            Ok(response) => {
+                let status = response.status().as_u16();
+                if is_retryable_status(status) {
+                    return Attempt::Retryable(anyhow::anyhow!("Notion returned retryable status {}", status));
+                }
+                match response.text() {
+                    Ok(body) => match serde_json::from_str::<Value>(&body) {
+                        Ok(value) => Attempt::Success(value),
+                        Err(e) => Attempt::Fatal(NotionError::Deserialization(e, Some(body)).into()),
+                    },
+                    Err(e) => Attempt::Fatal(NotionError::from(e).into()),
+                }
+            }
+            Err(e) if e.is_timeout() || e.is_connect() => Attempt::Retryable(e.into()),
+            Err(e) => Attempt::Fatal(NotionError::from(e).into()),
+        })
+    }
+
     /// List all users in the workspace.
-    pub async fn list_users(&self) -> Result<Value, reqwest::Error> {
+    pub fn list_users(&self) -> anyhow::Result<Value> {
         let url = format!("{}/users", self.base_url);
-        let response = self.client.get(&url).send().await?;
-        response.json().await
+        self.send_json(|client| client.get(&url))
     }
 
     /// Search for pages by title.
-    pub async fn search_pages(&self, query: &str) -> Result<Value, reqwest::Error> {
+    pub fn search_pages(&self, query: &str) -> anyhow::Result<Value> {
         let url = format!("{}/search", self.base_url);
         let body = json!({
             "query": query,
@@ -55,91 +84,256 @@ impl NotionClient {
                 "value": "page"
             }
         });
-        let response = self.client.post(&url).json(&body).send().await?;
-        response.json().await
+        self.send_json(|client| client.post(&url).json(&body))
     }
 
     /// Retrieve a database by ID.
-    pub async fn get_database(&self, database_id: &str) -> Result<Value, reqwest::Error> {
+    pub fn get_database(&self, database_id: &str) -> anyhow::Result<Value> {
         let url = format!("{}/databases/{}", self.base_url, database_id);
-        let response = self.client.get(&url).send().await?;
-        response.json().await
+        self.send_json(|client| client.get(&url))
     }
 
     /// Query a database with optional filters.
-    pub async fn query_database(
+    pub fn query_database(
         &self,
         database_id: &str,
         filter_params: Option<Value>,
-    ) -> Result<Value, reqwest::Error> {
+    ) -> anyhow::Result<Value> {
         let url = format!("{}/databases/{}/query", self.base_url, database_id);
         let body = match filter_params {
             Some(filter) => json!({ "filter": filter }),
             None => json!({}),
         };
-        let response = self.client.post(&url).json(&body).send().await?;
-        response.json().await
+        self.send_json(|client| client.post(&url).json(&body))
     }
 
     /// Query a specific datasource database with optional filters.
-    pub async fn query_datasource(
+    pub fn query_datasource(
         &self,
         source_id: &str,
         filter_params: Option<Value>,
-    ) -> Result<Value, reqwest::Error> {
+    ) -> anyhow::Result<Value> {
         let url = format!("{}/data_sources/{}/query", self.base_url, source_id);
         let body = filter_params.unwrap_or(json!({}));
-        let response = self.client.post(&url).json(&body).send().await?;
-        response.json().await
+        self.send_json(|client| client.post(&url).json(&body))
     }
 
     /// Retrieve a page by ID.
-    pub async fn get_page(&self, page_id: &str) -> Result<Value, reqwest::Error> {
+    pub fn get_page(&self, page_id: &str) -> anyhow::Result<Value> {
         let url = format!("{}/pages/{}", self.base_url, page_id);
-        let response = self.client.get(&url).send().await?;
-        response.json().await
+        self.send_json(|client| client.get(&url))
     }
 
     /// Get all child blocks of a page or block.
-    pub async fn get_block_children(&self, block_id: &str) -> Result<Value, reqwest::Error> {
+    pub fn get_block_children(&self, block_id: &str) -> anyhow::Result<Value> {
         let url = format!("{}/blocks/{}/children", self.base_url, block_id);
-        let response = self.client.get(&url).send().await?;
-        response.json().await
+        self.send_json(|client| client.get(&url))
     }
 }
 
-/// Extract tasks from a datasource response that have "To Do" or "Doing" status.
-pub fn extract_active_tasks(datasource_response: &Value) -> Vec<String> {
-    let mut tasks = Vec::new();
+/// Errors surfaced while extracting tasks out of a Notion datasource response, instead
+/// of silently yielding an empty `Vec` when the schema doesn't match what we expect.
+#[derive(Debug)]
+pub enum NotionError {
+    Http(reqwest::Error),
+    Deserialization(serde_json::Error, Option<String>),
+    NoSuchProperty(String),
+}
 
-    if let Some(results) = datasource_response
-        .get("results")
-        .and_then(|r| r.as_array())
-    {
-        for res in results {
-            let status_name = res
-                .get("properties")
-                .and_then(|p| p.get("Status"))
-                .and_then(|s| s.get("select"))
-                .and_then(|s| s.get("name"))
-                .and_then(|n| n.as_str());
-
-            if matches!(status_name, Some("To Do" | "Doing")) {
-                if let Some(titles) = res
-                    .get("properties")
-                    .and_then(|p| p.get("Name"))
-                    .and_then(|name_prop| name_prop.get("title"))
-                    .and_then(|title_arr| title_arr.as_array())
-                {
-                    for title in titles {
-                        if let Some(text) = title.get("plain_text").and_then(|t| t.as_str()) {
-                            tasks.push(text.to_string());
-                        }
-                    }
+impl fmt::Display for NotionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotionError::Http(e) => write!(f, "Notion HTTP request failed: {}", e),
+            NotionError::Deserialization(e, raw) => {
+                write!(f, "Failed to deserialize Notion response: {}", e)?;
+                if let Some(raw) = raw {
+                    write!(f, " (raw: {})", raw)?;
                 }
+                Ok(())
             }
+            NotionError::NoSuchProperty(name) => {
+                write!(f, "Notion page has no property named '{}'", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for NotionError {}
+
+impl From<reqwest::Error> for NotionError {
+    fn from(e: reqwest::Error) -> Self {
+        NotionError::Http(e)
+    }
+}
+
+impl From<serde_json::Error> for NotionError {
+    fn from(e: serde_json::Error) -> Self {
+        NotionError::Deserialization(e, None)
+    }
+}
+
+/// Status of a task, as read from either a `select`- or `status`-typed Notion property.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskStatus {
+    ToDo,
+    Doing,
+    Done,
+    Other(String),
+}
+
+impl TaskStatus {
+    fn from_name(name: &str) -> Self {
+        match name {
+            "To Do" => TaskStatus::ToDo,
+            "Doing" => TaskStatus::Doing,
+            "Done" => TaskStatus::Done,
+            other => TaskStatus::Other(other.to_string()),
+        }
+    }
+
+    /// Whether this status counts as actionable work for today's plan.
+    pub fn is_active(&self) -> bool {
+        matches!(self, TaskStatus::ToDo | TaskStatus::Doing)
+    }
+}
+
+impl fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskStatus::ToDo => write!(f, "To Do"),
+            TaskStatus::Doing => write!(f, "Doing"),
+            TaskStatus::Done => write!(f, "Done"),
+            TaskStatus::Other(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Task {
+    pub name: String,
+    pub status: TaskStatus,
+    pub due: Option<NaiveDate>,
+    pub priority: Option<String>,
+}
+
+/// Extracted scalar value of a single Notion property, resolved via its `"type"` field.
+enum PropertyValue {
+    Text(String),
+    Date(Option<NaiveDate>),
+}
+
+fn plain_text_of(value: &Value, key: &str) -> Option<String> {
+    value
+        .get(key)
+        .and_then(|a| a.as_array())
+        .and_then(|arr| arr.first())
+        .and_then(|t| t.get("plain_text"))
+        .and_then(|t| t.as_str())
+        .map(str::to_string)
+}
+
+/// Parse one entry of a page's `properties` object according to its Notion `"type"`
+/// discriminator, rather than assuming a fixed shape like `select`-only statuses.
+fn parse_property(name: &str, value: &Value) -> Result<PropertyValue, NotionError> {
+    let property_type = value
+        .get("type")
+        .and_then(|t| t.as_str())
+        .ok_or_else(|| NotionError::NoSuchProperty(name.to_string()))?;
+
+    match property_type {
+        "title" => Ok(PropertyValue::Text(plain_text_of(value, "title").unwrap_or_default())),
+        "rich_text" => Ok(PropertyValue::Text(
+            plain_text_of(value, "rich_text").unwrap_or_default(),
+        )),
+        "select" => Ok(PropertyValue::Text(
+            value
+                .get("select")
+                .and_then(|s| s.get("name"))
+                .and_then(|n| n.as_str())
+                .unwrap_or_default()
+                .to_string(),
+        )),
+        "status" => Ok(PropertyValue::Text(
+            value
+                .get("status")
+                .and_then(|s| s.get("name"))
+                .and_then(|n| n.as_str())
+                .unwrap_or_default()
+                .to_string(),
+        )),
+        "date" => Ok(PropertyValue::Date(
+            value
+                .get("date")
+                .and_then(|d| d.get("start"))
+                .and_then(|s| s.as_str())
+                .and_then(|s| NaiveDate::parse_from_str(&s[..10.min(s.len())], "%Y-%m-%d").ok()),
+        )),
+        other => Err(NotionError::NoSuchProperty(format!(
+            "{} (unsupported property type '{}')",
+            name, other
+        ))),
+    }
+}
+
+fn get_property<'a>(properties: &'a Value, name: &str) -> Result<&'a Value, NotionError> {
+    properties
+        .get(name)
+        .ok_or_else(|| NotionError::NoSuchProperty(name.to_string()))
+}
+
+fn parse_task(page: &Value) -> Result<Task, NotionError> {
+    let properties = page
+        .get("properties")
+        .ok_or_else(|| NotionError::NoSuchProperty("properties".to_string()))?;
+
+    let name = match parse_property("Name", get_property(properties, "Name")?)? {
+        PropertyValue::Text(text) => text,
+        PropertyValue::Date(_) => return Err(NotionError::NoSuchProperty("Name".to_string())),
+    };
+
+    let status = match parse_property("Status", get_property(properties, "Status")?)? {
+        PropertyValue::Text(text) => TaskStatus::from_name(&text),
+        PropertyValue::Date(_) => return Err(NotionError::NoSuchProperty("Status".to_string())),
+    };
+
+    let due = properties
+        .get("Due")
+        .and_then(|value| parse_property("Due", value).ok())
+        .and_then(|parsed| match parsed {
+            PropertyValue::Date(date) => date,
+            PropertyValue::Text(_) => None,
+        });
+
+    let priority = properties
+        .get("Priority")
+        .and_then(|value| parse_property("Priority", value).ok())
+        .and_then(|parsed| match parsed {
+            PropertyValue::Text(text) if !text.is_empty() => Some(text),
+            _ => None,
+        });
+
+    Ok(Task { name, status, due, priority })
+}
+
+/// Extract tasks from a datasource response that have "To Do" or "Doing" status.
+///
+/// A page that doesn't match the expected schema (a template/placeholder row, or a
+/// differently-named title property) is logged and skipped rather than aborting the
+/// whole fetch, so one malformed row doesn't take the device dark.
+pub fn extract_active_tasks(datasource_response: &Value) -> Result<Vec<Task>, NotionError> {
+    let results = datasource_response
+        .get("results")
+        .and_then(|r| r.as_array())
+        .ok_or_else(|| NotionError::NoSuchProperty("results".to_string()))?;
+
+    let mut tasks = Vec::new();
+    for page in results {
+        match parse_task(page) {
+            Ok(task) if task.status.is_active() => tasks.push(task),
+            Ok(_) => {}
+            Err(e) => warn!("Skipping Notion page that doesn't match the task schema: {}", e),
         }
     }
 
-    tasks
+    Ok(tasks)
 }
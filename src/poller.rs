@@ -0,0 +1,210 @@
+//! Periodic refresh loop
+//!
+//! Re-runs the fetch -> plan -> display pipeline on a fixed interval instead of once,
+//! so the e-paper doesn't show a stale schedule until a manual reset. Run status and
+//! retry counts are persisted to NVS so a reboot mid-backoff picks up where it left off.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use esp_idf_svc::nvs::{EspNvs, EspNvsPartition, NvsDefault};
+use log::{info, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::Duration;
+
+/// How finely `sleep_interruptibly` checks for a requested early wake-up.
+const REFRESH_POLL_STEP: Duration = Duration::from_secs(1);
+
+/// How often `sleep_interruptibly` calls `on_idle_tick` while waiting out an interval,
+/// so a live clock region can be kept current between full pipeline reruns.
+const IDLE_TICK_INTERVAL: Duration = Duration::from_secs(60);
+
+const NVS_NAMESPACE: &str = "poller";
+const STATUS_KEY: &str = "status";
+const RETRY_COUNT_KEY: &str = "retry_count";
+const LAST_RUN_KEY: &str = "last_run";
+const FAILURE_BACKOFF_UNIT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl RunStatus {
+    fn as_u8(self) -> u8 {
+        match self {
+            RunStatus::Pending => 0,
+            RunStatus::Running => 1,
+            RunStatus::Completed => 2,
+            RunStatus::Failed => 3,
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => RunStatus::Running,
+            2 => RunStatus::Completed,
+            3 => RunStatus::Failed,
+            _ => RunStatus::Pending,
+        }
+    }
+}
+
+/// Drives a fixed-interval poll loop around a fetch/plan/display pipeline, persisting
+/// run status, a monotonically-incremented retry counter, and the last successful run
+/// timestamp to NVS.
+pub struct Poller {
+    nvs: EspNvs<NvsDefault>,
+    interval: Duration,
+    retry_count: u32,
+    refresh_requested: Arc<AtomicBool>,
+}
+
+impl Poller {
+    pub fn new(nvs_partition: EspNvsPartition<NvsDefault>, interval: Duration) -> Result<Self> {
+        let nvs = EspNvs::new(nvs_partition, NVS_NAMESPACE, true)
+            .context("Failed to open poller NVS namespace")?;
+        let retry_count = nvs.get_u32(RETRY_COUNT_KEY).ok().flatten().unwrap_or(0);
+
+        Ok(Self {
+            nvs,
+            interval,
+            retry_count,
+            refresh_requested: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// A shared flag other subsystems (the RPC server's `refresh` method) can set to
+    /// wake the poller immediately instead of waiting out the rest of the interval.
+    pub fn refresh_handle(&self) -> Arc<AtomicBool> {
+        self.refresh_requested.clone()
+    }
+
+    pub fn status(&self) -> RunStatus {
+        self.nvs
+            .get_u8(STATUS_KEY)
+            .ok()
+            .flatten()
+            .map(RunStatus::from_u8)
+            .unwrap_or(RunStatus::Pending)
+    }
+
+    pub fn retry_count(&self) -> u32 {
+        self.retry_count
+    }
+
+    pub fn last_run(&self) -> Option<DateTime<Local>> {
+        self.nvs
+            .get_i64(LAST_RUN_KEY)
+            .ok()
+            .flatten()
+            .and_then(|ts| DateTime::from_timestamp(ts, 0))
+            .map(|dt| dt.with_timezone(&Local))
+    }
+
+    fn set_status(&mut self, status: RunStatus) {
+        if let Err(e) = self.nvs.set_u8(STATUS_KEY, status.as_u8()) {
+            warn!("Failed to persist poller status to NVS: {:?}", e);
+        }
+    }
+
+    fn set_retry_count(&mut self, count: u32) {
+        self.retry_count = count;
+        if let Err(e) = self.nvs.set_u32(RETRY_COUNT_KEY, count) {
+            warn!("Failed to persist poller retry count to NVS: {:?}", e);
+        }
+    }
+
+    fn record_success(&mut self) {
+        self.set_status(RunStatus::Completed);
+        self.set_retry_count(0);
+        if let Err(e) = self.nvs.set_i64(LAST_RUN_KEY, Local::now().timestamp()) {
+            warn!("Failed to persist poller last-run timestamp to NVS: {:?}", e);
+        }
+    }
+
+    fn record_failure(&mut self) {
+        self.set_status(RunStatus::Failed);
+        self.set_retry_count(self.retry_count + 1);
+    }
+
+    /// Sleep for `duration`, waking early (and clearing the flag) if a refresh was
+    /// requested in the meantime. Calls `on_idle_tick` every `IDLE_TICK_INTERVAL` so a
+    /// caller can keep a live clock region current without waiting for the next full
+    /// pipeline run.
+    fn sleep_interruptibly(&self, duration: Duration, mut on_idle_tick: impl FnMut()) {
+        let mut remaining = duration;
+        let mut since_last_tick = Duration::ZERO;
+        while remaining > Duration::ZERO {
+            if self.refresh_requested.swap(false, Ordering::SeqCst) {
+                info!("Immediate refresh requested, skipping remainder of poll interval");
+                return;
+            }
+            let step = remaining.min(REFRESH_POLL_STEP);
+            sleep(step);
+            remaining -= step;
+
+            since_last_tick += step;
+            if since_last_tick >= IDLE_TICK_INTERVAL {
+                since_last_tick = Duration::ZERO;
+                on_idle_tick();
+            }
+        }
+    }
+
+    /// Run `pipeline` on `interval`, calling `on_change` with the new result only when
+    /// it differs from the previous successful run (to spare e-paper refresh cycles).
+    /// On failure, increments the retry counter and backs off before the next attempt
+    /// instead of crashing the device. `on_tick` is called after every status change
+    /// (running, completed, failed) so a caller can mirror status into shared state,
+    /// e.g. for the RPC server's `get_status` method. `on_idle_tick` is called every
+    /// `IDLE_TICK_INTERVAL` while waiting out the interval or a failure backoff, so a
+    /// caller can do a cheap partial-refresh update (e.g. a live clock) in between the
+    /// full re-fetch/re-render cycles.
+    pub fn run_forever<T: PartialEq>(
+        &mut self,
+        mut pipeline: impl FnMut() -> Result<T>,
+        mut on_change: impl FnMut(&T) -> Result<()>,
+        mut on_tick: impl FnMut(RunStatus, u32, Option<DateTime<Local>>),
+        mut on_idle_tick: impl FnMut(),
+    ) -> ! {
+        let mut last_schedule: Option<T> = None;
+
+        loop {
+            self.set_status(RunStatus::Running);
+            on_tick(self.status(), self.retry_count, self.last_run());
+
+            match pipeline() {
+                Ok(schedule) => {
+                    self.record_success();
+                    on_tick(self.status(), self.retry_count, self.last_run());
+
+                    if last_schedule.as_ref() != Some(&schedule) {
+                        match on_change(&schedule) {
+                            Ok(()) => last_schedule = Some(schedule),
+                            Err(e) => warn!("Failed to render updated schedule: {:?}", e),
+                        }
+                    } else {
+                        info!("Schedule unchanged since last run, skipping redraw");
+                    }
+
+                    self.sleep_interruptibly(self.interval, &mut on_idle_tick);
+                }
+                Err(e) => {
+                    self.record_failure();
+                    on_tick(self.status(), self.retry_count, self.last_run());
+                    let backoff = (FAILURE_BACKOFF_UNIT * self.retry_count).min(self.interval);
+                    warn!(
+                        "Pipeline run failed (retry #{}), backing off {:?}: {}",
+                        self.retry_count, backoff, e
+                    );
+                    self.sleep_interruptibly(backoff, &mut on_idle_tick);
+                }
+            }
+        }
+    }
+}
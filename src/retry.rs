@@ -0,0 +1,202 @@
+//! Shared retry-with-backoff and client-side rate limiting for the crate's HTTP calls.
+//!
+//! Used by the Gemini, Calendar, and Notion clients so a single transient failure
+//! (429, 5xx, a dropped connection) doesn't abort the whole daily run.
+
+use log::warn;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Outcome of a single attempt: succeed, retry after a backoff, or give up immediately
+/// (e.g. a 4xx that retrying can never fix).
+pub enum Attempt<T> {
+    Success(T),
+    Retryable(anyhow::Error),
+    Fatal(anyhow::Error),
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Whether an HTTP status is worth retrying (rate limited or a transient server fault).
+pub fn is_retryable_status(status: u16) -> bool {
+    matches!(status, 429 | 500 | 502 | 503 | 504)
+}
+
+/// A cheap jitter source. Not cryptographically random, just enough to keep retries
+/// from multiple in-flight requests from lining up on the same delay.
+fn jitter(max: Duration) -> Duration {
+    let max_nanos = max.as_nanos() as u64;
+    if max_nanos == 0 {
+        return Duration::ZERO;
+    }
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or(0);
+    Duration::from_nanos(nanos % max_nanos)
+}
+
+/// Run `attempt` until it succeeds, is declared fatal, or `config.max_retries` retries
+/// are exhausted. Sleeps `base_delay * 2^n` (capped at `max_delay`, plus jitter)
+/// between retryable failures.
+pub fn retry_with_backoff<T>(
+    config: &RetryConfig,
+    mut attempt: impl FnMut() -> Attempt<T>,
+) -> anyhow::Result<T> {
+    let mut last_err = None;
+
+    for n in 0..=config.max_retries {
+        match attempt() {
+            Attempt::Success(value) => return Ok(value),
+            Attempt::Fatal(e) => return Err(e),
+            Attempt::Retryable(e) => {
+                warn!(
+                    "Attempt {}/{} failed, retrying: {}",
+                    n + 1,
+                    config.max_retries + 1,
+                    e
+                );
+                last_err = Some(e);
+                if n < config.max_retries {
+                    let backoff = (config.base_delay * (1u32 << n)).min(config.max_delay);
+                    std::thread::sleep(backoff + jitter(Duration::from_millis(250)));
+                }
+            }
+        }
+    }
+
+    Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Retry loop exhausted with no recorded error")))
+}
+
+/// Simple token-bucket limiter so bursts of requests to the same host are spaced out
+/// to respect provider RPM limits (e.g. the Gemini free tier).
+pub struct RateLimiter {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(capacity: f64, refill_per_sec: f64) -> Self {
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_sec,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// Block until a token is available, then consume it.
+    pub fn acquire(&mut self) {
+        self.refill();
+        if self.tokens < 1.0 {
+            let deficit = 1.0 - self.tokens;
+            std::thread::sleep(Duration::from_secs_f64(deficit / self.refill_per_sec));
+            self.refill();
+        }
+        self.tokens -= 1.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retryable_status_codes() {
+        assert!(is_retryable_status(429));
+        assert!(is_retryable_status(500));
+        assert!(is_retryable_status(503));
+        assert!(!is_retryable_status(200));
+        assert!(!is_retryable_status(404));
+    }
+
+    #[test]
+    fn succeeds_without_retrying() {
+        let mut calls = 0;
+        let result = retry_with_backoff(&RetryConfig::default(), || {
+            calls += 1;
+            Attempt::Success(42)
+        });
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn fatal_gives_up_immediately() {
+        let mut calls = 0;
+        let result: anyhow::Result<()> = retry_with_backoff(&RetryConfig::default(), || {
+            calls += 1;
+            Attempt::Fatal(anyhow::anyhow!("nope"))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn retries_up_to_the_configured_max_then_gives_up() {
+        let config = RetryConfig {
+            max_retries: 2,
+            base_delay: Duration::from_millis(0),
+            max_delay: Duration::from_millis(0),
+        };
+        let mut calls = 0;
+        let result: anyhow::Result<()> = retry_with_backoff(&config, || {
+            calls += 1;
+            Attempt::Retryable(anyhow::anyhow!("still failing"))
+        });
+        assert!(result.is_err());
+        // One initial attempt plus `max_retries` retries.
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn succeeds_after_a_retryable_failure() {
+        let config = RetryConfig {
+            max_retries: 3,
+            base_delay: Duration::from_millis(0),
+            max_delay: Duration::from_millis(0),
+        };
+        let mut calls = 0;
+        let result = retry_with_backoff(&config, || {
+            calls += 1;
+            if calls < 2 {
+                Attempt::Retryable(anyhow::anyhow!("transient"))
+            } else {
+                Attempt::Success("ok")
+            }
+        });
+        assert_eq!(result.unwrap(), "ok");
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn jitter_never_exceeds_its_bound() {
+        for _ in 0..20 {
+            assert!(jitter(Duration::from_millis(250)) < Duration::from_millis(250));
+        }
+        assert_eq!(jitter(Duration::ZERO), Duration::ZERO);
+    }
+}
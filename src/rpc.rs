@@ -0,0 +1,188 @@
+//! On-device JSON-RPC status/control server
+//!
+//! Exposes the latest computed schedule and poller status over HTTP so the prioritizer
+//! can be inspected or driven from a phone or laptop on the same WiFi, instead of having
+//! to read the serial log. Binds dual-stack (v4/v6) on one port via `EspHttpServer`'s
+//! default configuration, and publishes an OpenRPC-style discovery document at
+//! `/rpc.json`, built from the same `ResponseSchema` derivation the Gemini client uses.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use esp_idf_svc::http::server::{Configuration as HttpServerConfig, EspHttpServer};
+use esp_idf_svc::http::Method;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::calendar::FreeSlot;
+use crate::gemini::{ResponseSchema, ScheduleItem};
+use crate::poller::RunStatus;
+
+const MAX_REQUEST_BODY_BYTES: usize = 4096;
+
+/// `FreeSlot` carries `NaiveDateTime`s meant for internal calculation; the RPC surface
+/// reports just the clock times a client needs, mirroring how the display formats them.
+#[derive(Debug, Clone, Serialize)]
+pub struct FreeSlotSummary {
+    pub start: String,
+    pub end: String,
+}
+
+impl From<&FreeSlot> for FreeSlotSummary {
+    fn from(slot: &FreeSlot) -> Self {
+        Self {
+            start: slot.start.format("%H:%M").to_string(),
+            end: slot.end.format("%H:%M").to_string(),
+        }
+    }
+}
+
+impl ResponseSchema for FreeSlotSummary {
+    fn response_schema() -> Value {
+        json!({
+            "type": "ARRAY",
+            "items": {
+                "type": "OBJECT",
+                "properties": {
+                    "start": { "type": "STRING", "pattern": "^\\d{1,2}:\\d{2}$" },
+                    "end": { "type": "STRING", "pattern": "^\\d{1,2}:\\d{2}$" }
+                },
+                "required": ["start", "end"]
+            }
+        })
+    }
+}
+
+/// Latest pipeline output and poller status, updated by the main loop each tick and
+/// read by RPC handlers running on the HTTP server's own thread.
+#[derive(Default)]
+pub struct RpcState {
+    pub schedule: Vec<ScheduleItem>,
+    pub free_slots: Vec<FreeSlotSummary>,
+    pub status: Option<RunStatus>,
+    pub retry_count: u32,
+    pub last_run: Option<DateTime<Local>>,
+}
+
+/// Start the RPC server and hand back its `EspHttpServer`; the caller must keep this
+/// alive for as long as the server should keep serving requests.
+pub fn start(
+    state: Arc<Mutex<RpcState>>,
+    refresh_requested: Arc<AtomicBool>,
+) -> Result<EspHttpServer<'static>> {
+    let mut server = EspHttpServer::new(&HttpServerConfig::default())
+        .context("Failed to start RPC HTTP server")?;
+
+    server
+        .fn_handler("/rpc.json", Method::Get, |request| {
+            let mut response = request.into_ok_response()?;
+            response.write(openrpc_document().to_string().as_bytes())?;
+            Ok(())
+        })
+        .context("Failed to register /rpc.json handler")?;
+
+    server
+        .fn_handler("/rpc", Method::Post, move |mut request| {
+            let mut body = Vec::new();
+            let mut buf = [0u8; 512];
+            loop {
+                match request.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => body.extend_from_slice(&buf[..n]),
+                    Err(e) => return Err(anyhow::anyhow!("Failed to read RPC request body: {:?}", e)),
+                }
+                if body.len() >= MAX_REQUEST_BODY_BYTES {
+                    break;
+                }
+            }
+
+            let reply = handle_request(&body, &state, &refresh_requested);
+
+            let mut response = request.into_ok_response()?;
+            response.write(reply.to_string().as_bytes())?;
+            Ok(())
+        })
+        .context("Failed to register /rpc handler")?;
+
+    Ok(server)
+}
+
+/// Dispatch a single JSON-RPC 2.0 request against the shared state, returning the
+/// envelope to write back verbatim.
+fn handle_request(body: &[u8], state: &Mutex<RpcState>, refresh_requested: &AtomicBool) -> Value {
+    let request: Value = match serde_json::from_slice(body) {
+        Ok(value) => value,
+        Err(e) => return rpc_error(Value::Null, -32700, &format!("Parse error: {}", e)),
+    };
+
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = request.get("method").and_then(Value::as_str).unwrap_or("");
+
+    let result = match method {
+        "get_schedule" => json!(state.lock().unwrap().schedule),
+        "get_free_slots" => json!(state.lock().unwrap().free_slots),
+        "get_status" => {
+            let state = state.lock().unwrap();
+            json!({
+                "status": state.status.map(|s| format!("{:?}", s)),
+                "retry_count": state.retry_count,
+                "last_run": state.last_run.map(|t| t.to_rfc3339()),
+            })
+        }
+        "refresh" => {
+            refresh_requested.store(true, Ordering::SeqCst);
+            json!({ "triggered": true })
+        }
+        other => return rpc_error(id, -32601, &format!("Method not found: {}", other)),
+    };
+
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn rpc_error(id: Value, code: i32, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// An OpenRPC-style discovery document so clients don't have to guess method shapes.
+fn openrpc_document() -> Value {
+    json!({
+        "openrpc": "1.2.6",
+        "info": { "title": "Daily Prioritizer RPC", "version": "1.0.0" },
+        "methods": [
+            {
+                "name": "get_schedule",
+                "summary": "Return the most recently computed schedule.",
+                "result": { "name": "schedule", "schema": ScheduleItem::response_schema() }
+            },
+            {
+                "name": "get_free_slots",
+                "summary": "Return today's free time slots from the last calendar fetch.",
+                "result": { "name": "free_slots", "schema": FreeSlotSummary::response_schema() }
+            },
+            {
+                "name": "get_status",
+                "summary": "Return the poller's last run time, status, and retry count.",
+                "result": {
+                    "name": "status",
+                    "schema": {
+                        "type": "OBJECT",
+                        "properties": {
+                            "status": { "type": "STRING", "nullable": true },
+                            "retry_count": { "type": "INTEGER" },
+                            "last_run": { "type": "STRING", "nullable": true }
+                        }
+                    }
+                }
+            },
+            {
+                "name": "refresh",
+                "summary": "Trigger an immediate pipeline re-run and display redraw.",
+                "result": {
+                    "name": "triggered",
+                    "schema": { "type": "OBJECT", "properties": { "triggered": { "type": "BOOLEAN" } } }
+                }
+            }
+        ]
+    })
+}
@@ -0,0 +1,163 @@
+//! Environmental sensor module
+//!
+//! Reads a DHT22 temperature/humidity sensor over its single-wire protocol on each
+//! poll cycle and keeps a rolling window of readings so the display can plot recent
+//! history, mirroring the measurements view from the raspi-oled sibling project.
+
+use anyhow::{bail, Result};
+use esp_idf_hal::delay::FreeRtos;
+use esp_idf_hal::gpio::{Gpio4, InputOutput, PinDriver, Pull};
+use esp_idf_sys::esp_timer_get_time;
+use std::collections::VecDeque;
+
+/// How long to hold the data line low to signal a read request.
+const START_SIGNAL_LOW_MS: u32 = 2;
+/// A high pulse longer than this (microseconds) decodes to a binary 1; shorter is 0.
+const BIT_THRESHOLD_US: i64 = 50;
+/// Give up waiting on a pulse edge after this long (a disconnected or stuck sensor).
+const PULSE_TIMEOUT_US: i64 = 200;
+
+/// How many readings to keep for the history graph.
+pub const HISTORY_LEN: usize = 48;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Reading {
+    pub temperature_c: f32,
+    pub humidity_pct: f32,
+}
+
+/// Drives a DHT22 on a single GPIO pin and keeps a rolling window of its readings.
+pub struct Dht22<'a> {
+    pin: PinDriver<'a, Gpio4, InputOutput>,
+    history: VecDeque<Reading>,
+}
+
+impl<'a> Dht22<'a> {
+    pub fn new(pin: Gpio4) -> Result<Self> {
+        // The DHT single-wire protocol needs an open-drain line: both the MCU and the
+        // sensor only ever pull it low and rely on a pull-up to bring it back high, so
+        // each side can read what the other is driving. A push-pull `input_output` here
+        // would have the MCU actively hold the line high and the sensor could never pull
+        // it low to ack, timing out on every read.
+        let mut pin = PinDriver::input_output_od(pin)?;
+        pin.set_pull(Pull::Up)?;
+        Ok(Self {
+            pin,
+            history: VecDeque::new(),
+        })
+    }
+
+    /// Take a fresh reading, push it into the rolling history window, and return it.
+    pub fn read(&mut self) -> Result<Reading> {
+        let reading = self.read_raw()?;
+        self.history.push_back(reading);
+        while self.history.len() > HISTORY_LEN {
+            self.history.pop_front();
+        }
+        Ok(reading)
+    }
+
+    pub fn history(&self) -> &VecDeque<Reading> {
+        &self.history
+    }
+
+    /// Pull the line low for `START_SIGNAL_LOW_MS` to request a reading, then time the sensor's acknowledgement
+    /// and the 40 data-bit pulses that follow, verifying the trailing checksum byte.
+    fn read_raw(&mut self) -> Result<Reading> {
+        self.pin.set_low()?;
+        FreeRtos::delay_ms(START_SIGNAL_LOW_MS);
+        self.pin.set_high()?;
+
+        // Sensor acknowledges with a low pulse then a high pulse before streaming data.
+        self.wait_for_level(false)?;
+        self.wait_for_level(true)?;
+        self.wait_for_level(false)?;
+
+        let mut bits = [0u8; 40];
+        for bit in bits.iter_mut() {
+            self.wait_for_level(true)?;
+            let high_start = now_us();
+            self.wait_for_level(false)?;
+            *bit = if now_us() - high_start > BIT_THRESHOLD_US { 1 } else { 0 };
+        }
+
+        let mut bytes = [0u8; 5];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            for b in 0..8 {
+                *byte = (*byte << 1) | bits[i * 8 + b];
+            }
+        }
+
+        decode_reading(bytes)
+    }
+
+    fn wait_for_level(&self, high: bool) -> Result<()> {
+        let start = now_us();
+        loop {
+            if self.pin.is_high()? == high {
+                return Ok(());
+            }
+            if now_us() - start > PULSE_TIMEOUT_US {
+                bail!(
+                    "Timed out waiting for DHT22 {} pulse",
+                    if high { "high" } else { "low" }
+                );
+            }
+        }
+    }
+}
+
+/// Verify the checksum and decode the 5 raw bytes streamed by a DHT22 (2 bytes humidity,
+/// 2 bytes temperature with a sign bit in the top of the high byte, 1 checksum byte).
+fn decode_reading(bytes: [u8; 5]) -> Result<Reading> {
+    let checksum = bytes[0]
+        .wrapping_add(bytes[1])
+        .wrapping_add(bytes[2])
+        .wrapping_add(bytes[3]);
+    if checksum != bytes[4] {
+        bail!("DHT22 checksum mismatch (got {}, expected {})", checksum, bytes[4]);
+    }
+
+    let humidity_pct = u16::from_be_bytes([bytes[0], bytes[1]]) as f32 / 10.0;
+    let raw_temp = u16::from_be_bytes([bytes[2], bytes[3]]);
+    let magnitude = (raw_temp & 0x7FFF) as f32 / 10.0;
+    let temperature_c = if raw_temp & 0x8000 != 0 { -magnitude } else { magnitude };
+
+    Ok(Reading {
+        temperature_c,
+        humidity_pct,
+    })
+}
+
+fn now_us() -> i64 {
+    unsafe { esp_timer_get_time() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_a_valid_reading() {
+        // 65.2% humidity, 23.1C: bytes are big-endian tenths, checksum is the low byte
+        // of the sum of the first four.
+        let bytes = [0x02, 0x8C, 0x00, 0xE7, (0x02 + 0x8C + 0x00 + 0xE7) as u8];
+        let reading = decode_reading(bytes).unwrap();
+        assert!((reading.humidity_pct - 65.2).abs() < 0.01);
+        assert!((reading.temperature_c - 23.1).abs() < 0.01);
+    }
+
+    #[test]
+    fn decodes_a_negative_temperature() {
+        // Sign bit set in the high byte of the temperature word: -10.5C.
+        let bytes = [0x01, 0x90, 0x80, 0x69, (0x01 + 0x90 + 0x80 + 0x69) as u8];
+        let reading = decode_reading(bytes).unwrap();
+        assert!((reading.temperature_c - (-10.5)).abs() < 0.01);
+    }
+
+    #[test]
+    fn rejects_a_bad_checksum() {
+        let bytes = [0x02, 0x8C, 0x00, 0xE7, 0x00];
+        assert!(decode_reading(bytes).is_err());
+    }
+}
@@ -1,5 +1,9 @@
 mod font;
 
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::{OriginDimensions, Size};
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::Pixel;
 use esp_idf_hal::delay::FreeRtos;
 use esp_idf_hal::gpio::{Gpio9, Gpio10, Gpio11, Gpio12, Gpio13, Gpio14, Gpio46, Input, Output, PinDriver};
 use esp_idf_hal::peripherals::Peripherals;
@@ -179,7 +183,6 @@ impl<'a> Epd<'a> {
     }
 
     /// Turn on the display (4-gray mode)
-    #[allow(dead_code)]
     pub fn turn_on_display_4gray(&mut self) {
         self.send_command(0x22); // Display Update Control
         self.send_data(0xCF);
@@ -209,6 +212,16 @@ impl<'a> Epd<'a> {
         self.send_command(0x11);
         self.send_data(0x03); // X-mode
 
+        self.set_full_screen_ram_window();
+        self.read_busy();
+    }
+
+    /// Reset the controller's RAM address window (cmds 0x44/0x45) and counters (0x4E/0x4F)
+    /// to cover the whole panel. `display_partial_region` narrows this window to a small
+    /// band and never restores it, so a full refresh (`display`/`clear`) needs to set it
+    /// back before writing, or it streams its full frame into the stale partial band and
+    /// wraps instead of covering the panel.
+    fn set_full_screen_ram_window(&mut self) {
         self.send_command(0x44);
         self.send_data(0x00);
         self.send_data(0x31);
@@ -225,7 +238,6 @@ impl<'a> Epd<'a> {
         self.send_command(0x4F);
         self.send_data(0x00);
         self.send_data(0x00);
-        self.read_busy();
     }
 
     /// Load the lookup table for display waveforms
@@ -267,6 +279,8 @@ impl<'a> Epd<'a> {
         let buf_size = (self.height * linewidth) as usize;
         let white_buf = vec![0xFF_u8; buf_size];
 
+        self.set_full_screen_ram_window();
+
         self.send_command(0x24);
         self.send_data_bulk(&white_buf);
 
@@ -278,6 +292,8 @@ impl<'a> Epd<'a> {
 
     /// Display an image buffer on the screen
     pub fn display(&mut self, image: &[u8]) {
+        self.set_full_screen_ram_window();
+
         self.send_command(0x24);
         self.send_data_bulk(image);
 
@@ -322,6 +338,85 @@ impl<'a> Epd<'a> {
         self.turn_on_display_partial();
     }
 
+    /// Partial display update of a single sub-rectangle, so a small region (a live
+    /// clock, the agenda's "now" marker) can be refreshed without the flicker and delay
+    /// of redrawing the whole panel. `x`/`w` must be byte-aligned (multiples of 8), and
+    /// `data` must hold exactly `(w / 8) * h` bytes of MONO_HLSB-packed pixels, e.g. from
+    /// `FrameBuffer::crop_bytes`.
+    pub fn display_partial_region(&mut self, x: u32, y: u32, w: u32, h: u32, data: &[u8]) {
+        let x_start_byte = (x / 8) as u8;
+        let x_end_byte = ((x + w) / 8 - 1) as u8;
+        let y_start = y as u16;
+        let y_end = (y + h - 1) as u16;
+
+        self.send_command(0x3C); // BorderWaveform
+        self.send_data(0x80);
+
+        self.send_command(0x21); // Display update control
+        self.send_data(0x00);
+        self.send_data(0x00);
+
+        self.send_command(0x44); // RAM X address window
+        self.send_data(x_start_byte);
+        self.send_data(x_end_byte);
+
+        self.send_command(0x45); // RAM Y address window
+        self.send_data((y_start & 0xFF) as u8);
+        self.send_data((y_start >> 8) as u8);
+        self.send_data((y_end & 0xFF) as u8);
+        self.send_data((y_end >> 8) as u8);
+
+        self.send_command(0x4E); // RAM X address counter
+        self.send_data(x_start_byte);
+
+        self.send_command(0x4F); // RAM Y address counter
+        self.send_data((y_start & 0xFF) as u8);
+        self.send_data((y_start >> 8) as u8);
+
+        self.send_command(0x24); // WRITE_RAM
+        self.send_data_bulk(data);
+        self.turn_on_display_partial();
+    }
+
+    /// Display a `GrayFrameBuffer` in 4-gray mode. Splits each 2-bit pixel into its high
+    /// and low bit, sent to the display's two RAM planes (0x24, 0x26) the same way
+    /// `display` sends a 1bpp buffer to both, loads the grayscale waveform via `LUT_ALL`,
+    /// then activates the 4-gray update sequence.
+    #[allow(dead_code)]
+    pub fn display_4gray(&mut self, image: &GrayFrameBuffer) {
+        let pixel_count = (self.width * self.height) as usize;
+        let plane_size = pixel_count / 8;
+        let mut high_plane = vec![0u8; plane_size];
+        let mut low_plane = vec![0u8; plane_size];
+
+        for i in 0..pixel_count {
+            let level = image.level_at(i);
+            let plane_byte = i / 8;
+            let bit = 0x80 >> (i % 8);
+            if level & 0x02 != 0 {
+                high_plane[plane_byte] |= bit;
+            }
+            if level & 0x01 != 0 {
+                low_plane[plane_byte] |= bit;
+            }
+        }
+
+        self.set_full_screen_ram_window();
+
+        self.send_command(0x24);
+        self.send_data_bulk(&high_plane);
+
+        self.send_command(0x26);
+        self.send_data_bulk(&low_plane);
+
+        self.send_command(0x32); // Write LUT register
+        for &byte in LUT_ALL.iter() {
+            self.send_data(byte);
+        }
+
+        self.turn_on_display_4gray();
+    }
+
     /// Put the display into deep sleep mode
     pub fn sleep(&mut self) {
         self.send_command(commands::DEEP_SLEEP);
@@ -341,6 +436,7 @@ impl<'a> Epd<'a> {
 }
 
 /// Simple framebuffer for MONO_HLSB format
+#[derive(Clone)]
 pub struct FrameBuffer {
     buffer: Vec<u8>,
     width: u32,
@@ -358,6 +454,13 @@ impl FrameBuffer {
         }
     }
 
+    /// Wrap already-packed MONO_HLSB bytes as a framebuffer, e.g. a region cropped out
+    /// of another buffer with `crop_bytes`, so it can be drawn into without copying the
+    /// whole source buffer first.
+    pub fn from_bytes(width: u32, height: u32, buffer: Vec<u8>) -> Self {
+        Self { buffer, width, height }
+    }
+
     /// Fill the entire buffer with a color (0 = black, 1 = white)
     pub fn fill(&mut self, color: u8) {
         let fill_byte = if color == 0 { 0x00 } else { 0xFF };
@@ -466,6 +569,119 @@ impl FrameBuffer {
     pub fn buffer(&self) -> &[u8] {
         &self.buffer
     }
+
+    /// Extract a byte-aligned sub-rectangle of the buffer for a partial display update.
+    /// `x` and `w` must be multiples of 8, since the display's RAM addressing windows
+    /// are specified in 8-pixel bytes.
+    pub fn crop_bytes(&self, x: u32, y: u32, w: u32, h: u32) -> Vec<u8> {
+        debug_assert_eq!(x % 8, 0, "crop_bytes x must be byte-aligned");
+        debug_assert_eq!(w % 8, 0, "crop_bytes w must be byte-aligned");
+
+        let row_bytes = (w / 8) as usize;
+        let mut cropped = Vec::with_capacity(row_bytes * h as usize);
+        for row in 0..h {
+            let start = ((x + (y + row) * self.width) / 8) as usize;
+            cropped.extend_from_slice(&self.buffer[start..start + row_bytes]);
+        }
+        cropped
+    }
+}
+
+/// Framebuffer storing 4 gray levels (2 bits per pixel, 4 pixels packed per byte), for
+/// `Epd::display_4gray`. Levels run `0` (black) to `3` (white), with `1`/`2` the two
+/// intermediate grays, which would let the agenda shade tentative events differently
+/// from busy and free ones.
+///
+/// Not wired into the agenda renderer yet: `update_live_regions`'s periodic clock/now-marker
+/// partial refresh (see `main.rs`) only knows how to crop and re-send a 1bpp `FrameBuffer`
+/// region via `display_partial_region`, which writes a single RAM plane's on/off bits, not
+/// the two gray-level planes `display_4gray` needs. Switching the agenda's full-refresh frame
+/// to `GrayFrameBuffer` would break that partial-refresh path, so this stays available for a
+/// future full-refresh-only render mode rather than being adopted by the live agenda today.
+#[allow(dead_code)]
+pub struct GrayFrameBuffer {
+    buffer: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+#[allow(dead_code)]
+impl GrayFrameBuffer {
+    /// Create a new grayscale framebuffer, filled white (level 3)
+    pub fn new(width: u32, height: u32) -> Self {
+        let size = (width * height / 4) as usize;
+        Self {
+            buffer: vec![0xFF; size],
+            width,
+            height,
+        }
+    }
+
+    /// Fill the entire buffer with one gray level (0-3)
+    pub fn fill(&mut self, level: u8) {
+        let level = level & 0x03;
+        let fill_byte = level | (level << 2) | (level << 4) | (level << 6);
+        for byte in &mut self.buffer {
+            *byte = fill_byte;
+        }
+    }
+
+    /// Set a single pixel to a gray level (0-3)
+    pub fn pixel(&mut self, x: u32, y: u32, level: u8) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let idx = ((x + y * self.width) / 4) as usize;
+        let shift = 6 - 2 * (x % 4);
+        let mask = !(0x03 << shift);
+        self.buffer[idx] = (self.buffer[idx] & mask) | ((level & 0x03) << shift);
+    }
+
+    /// Gray level (0-3) of the pixel at flat index `i` (`x + y * width`)
+    fn level_at(&self, i: usize) -> u8 {
+        let byte = self.buffer[i / 4];
+        let shift = 6 - 2 * (i % 4);
+        (byte >> shift) & 0x03
+    }
+
+    /// Get the raw buffer
+    pub fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+/// Lets embedded-graphics know the buffer's extent, so primitives like `Rectangle` and
+/// `Text` can be positioned without the caller tracking width/height separately.
+impl OriginDimensions for FrameBuffer {
+    fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+}
+
+/// Maps `BinaryColor::On`/`Off` onto the existing MONO_HLSB convention (`0` = black,
+/// `1` = white), so embedded-graphics primitives and fonts (`MonoTextStyleBuilder`,
+/// `FONT_6X9`, `FONT_10X20`, `Rectangle`, `Line`, `Text`) draw into the same buffer the
+/// bespoke `pixel`/`line`/`rect`/`text` helpers above already write to.
+impl DrawTarget for FrameBuffer {
+    type Color = BinaryColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if point.x < 0 || point.y < 0 {
+                continue;
+            }
+            let raw_color = match color {
+                BinaryColor::On => 0,
+                BinaryColor::Off => 1,
+            };
+            self.pixel(point.x as u32, point.y as u32, raw_color);
+        }
+        Ok(())
+    }
 }
 
 fn main() -> anyhow::Result<()> {
@@ -541,3 +757,43 @@ fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crops_a_byte_aligned_region() {
+        let mut fb = FrameBuffer::new(16, 4);
+        fb.fill(1); // all white
+        fb.fill_rect(8, 1, 8, 2, 0); // black 8x2 block in the cropped region
+
+        let cropped = fb.crop_bytes(8, 1, 8, 2);
+
+        // 8 wide = 1 byte per row, 2 rows, all black.
+        assert_eq!(cropped, vec![0x00, 0x00]);
+    }
+
+    #[test]
+    fn crop_excludes_rows_and_columns_outside_the_region() {
+        let mut fb = FrameBuffer::new(16, 2);
+        fb.fill(1); // all white
+        fb.pixel(0, 0, 0); // outside the cropped region
+
+        let cropped = fb.crop_bytes(8, 0, 8, 1);
+
+        assert_eq!(cropped, vec![0xFF]);
+    }
+
+    #[test]
+    fn full_width_crop_round_trips_through_from_bytes() {
+        let mut fb = FrameBuffer::new(16, 2);
+        fb.fill(1);
+        fb.pixel(3, 1, 0);
+
+        let cropped = fb.crop_bytes(0, 0, 16, 2);
+        let rebuilt = FrameBuffer::from_bytes(16, 2, cropped);
+
+        assert_eq!(rebuilt.buffer(), fb.buffer());
+    }
+}